@@ -0,0 +1,47 @@
+use {
+    criterion::{criterion_group, criterion_main, Criterion},
+    hashring::{DefaultHashBuilder, HashRing, RingHasher, XxHashBuilder},
+    std::hint::black_box,
+};
+
+fn ring<S: RingHasher>(hash_builder: S) -> HashRing<String, S> {
+    let mut ring = HashRing::with_hasher(hash_builder);
+
+    for node in 0..1_000 {
+        ring.add_node_unchecked(format!("node-{node}"));
+    }
+
+    ring.sort();
+    ring
+}
+
+fn get_by_hash(c: &mut Criterion) {
+    let default = ring(DefaultHashBuilder);
+    let xxhash = ring(XxHashBuilder::default());
+    let keys = (0..10_000u64)
+        .map(|key| format!("key-{key}"))
+        .collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("get_by_hash");
+
+    group.bench_function("default", |b| {
+        b.iter(|| {
+            for key in black_box(&keys) {
+                black_box(default.get_by_hash(key).unwrap());
+            }
+        })
+    });
+
+    group.bench_function("xxhash", |b| {
+        b.iter(|| {
+            for key in black_box(&keys) {
+                black_box(xxhash.get_by_hash(key).unwrap());
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, get_by_hash);
+criterion_main!(benches);
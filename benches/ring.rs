@@ -0,0 +1,64 @@
+use {
+    criterion::{criterion_group, criterion_main, Criterion},
+    hashring::HashRing,
+    std::hint::black_box,
+};
+
+fn ring(nodes: usize) -> HashRing<String> {
+    let mut ring = HashRing::new();
+
+    for node in 0..nodes {
+        ring.add_node_unchecked(format!("node-{node}"));
+    }
+
+    ring.sort();
+    ring
+}
+
+fn owners_of(c: &mut Criterion) {
+    let ring = ring(1_000);
+    let keys = (0..10_000u64).collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("owners_of");
+
+    group.bench_function("batched", |b| b.iter(|| ring.owners_of(black_box(&keys))));
+
+    group.bench_function("per_key", |b| {
+        b.iter(|| {
+            black_box(&keys)
+                .iter()
+                .map(|key| ring.owner_index(key).unwrap())
+                .collect::<Vec<_>>()
+        })
+    });
+
+    group.finish();
+}
+
+fn get_by_key(c: &mut Criterion) {
+    let ring = ring(100_000);
+    let keys = (0..10_000u64).map(|key| ring.key(&key)).collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("get_by_key");
+
+    group.bench_function("binary", |b| {
+        b.iter(|| {
+            for key in black_box(&keys) {
+                black_box(ring.get_by_key(key).unwrap());
+            }
+        })
+    });
+
+    group.bench_function("interpolated", |b| {
+        b.iter(|| {
+            for key in black_box(&keys) {
+                black_box(ring.get_by_key_interpolated(key).unwrap());
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, owners_of, get_by_key);
+criterion_main!(benches);
@@ -0,0 +1,97 @@
+use {
+    super::{Error, HashRing, NodeRef, RingHasher},
+    core::hash::Hash,
+};
+
+/// Two-level router, routing through an outer ring of shards, and then the
+/// inner ring of the selected shard. See `HashRing::composite()`.
+pub struct CompositeRing<'a, T, S: RingHasher, F> {
+    outer: &'a HashRing<T, S>,
+    inner_for: F,
+}
+
+impl<'a, T, S, F, I, R> CompositeRing<'a, T, S, F>
+where
+    T: Hash,
+    S: RingHasher,
+    F: Fn(&'a T) -> &'a HashRing<I, R>,
+    I: Hash + 'a,
+    R: RingHasher + 'a,
+{
+    /// Returns the `NodeRef` for the node containing `data_key` within the
+    /// shard containing `outer_key`, or an error if either ring is empty.
+    pub fn route<U, V>(&self, outer_key: &U, data_key: &V) -> Result<NodeRef<'a, I, R>, Error>
+    where
+        U: Hash,
+        V: Hash,
+    {
+        let index = self.outer.owner_index(outer_key)?;
+
+        (self.inner_for)(&self.outer.data[index].data).get_by_hash(data_key)
+    }
+}
+
+impl<T, S> HashRing<T, S>
+where
+    T: Hash,
+    S: RingHasher,
+{
+    /// Returns a two-level router, using this ring to select a shard, and
+    /// `inner_for` to get the shard's own ring of nodes. This packages the
+    /// common pattern of sharding by e.g. tenant, and then by key within the
+    /// tenant's shard.
+    pub fn composite<F, I, R>(&self, inner_for: F) -> CompositeRing<'_, T, S, F>
+    where
+        F: Fn(&T) -> &HashRing<I, R>,
+        R: RingHasher,
+    {
+        CompositeRing {
+            outer: self,
+            inner_for,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::collections::HashMap};
+
+    #[test]
+    fn composite_routing() {
+        let mut outer = HashRing::new();
+        let mut inners = HashMap::new();
+
+        for (shard, nodes) in [("shard-a", 0..4), ("shard-b", 100..104)] {
+            outer.add_node(shard).unwrap();
+
+            let mut inner: HashRing<u32> = HashRing::new();
+            for node in nodes {
+                inner.add_node(node).unwrap();
+            }
+
+            inners.insert(shard, inner);
+        }
+
+        let composite = outer.composite(|shard| &inners[shard]);
+
+        for tenant in 0..20 {
+            let shard = *outer.get_by_hash(&tenant).unwrap().data();
+
+            for key in 0..20 {
+                assert_eq!(
+                    composite.route(&tenant, &key).unwrap().data(),
+                    inners[shard].get_by_hash(&key).unwrap().data()
+                );
+            }
+        }
+
+        let empty: HashRing<&str> = HashRing::new();
+        assert_eq!(
+            empty
+                .composite(|shard| &inners[shard])
+                .route(&1, &1)
+                .unwrap_err(),
+            Error::NodeNotFound
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use {
+    super::{Error, HashRing, RingHasher},
+    alloc::vec::Vec,
+    core::hash::Hash,
+};
+
+/// Object-safe view of a `HashRing` with 64-bit keys.
+///
+/// Erases the hash builder type, so rings using different `RingHasher`s can be
+/// stored together, e.g. in a `Vec<Box<dyn DynRing<T>>>`. Since generic lookup
+/// keys can't be passed through a trait object, lookups take the key's bytes.
+pub trait DynRing<T> {
+    /// Returns a clone of the data of the node containing `key`, or an error if
+    /// the hash ring is empty.
+    fn get_owned(&self, key: &[u8]) -> Result<T, Error>;
+
+    /// Get the number of nodes in the hash ring.
+    fn len(&self) -> usize;
+
+    /// Returns true if the ring has no elements.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the keys of all nodes, in ring order.
+    fn keys(&self) -> Vec<u64>;
+}
+
+impl<T, S> DynRing<T> for HashRing<T, S>
+where
+    T: Hash + Clone,
+    S: RingHasher<Key = u64>,
+{
+    fn get_owned(&self, key: &[u8]) -> Result<T, Error> {
+        HashRing::get_owned(self, &key)
+    }
+
+    fn len(&self) -> usize {
+        HashRing::len(self)
+    }
+
+    fn keys(&self) -> Vec<u64> {
+        self.data.iter().map(|node| node.key).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, siphasher::sip::SipHasher, std::hash::BuildHasher};
+
+    #[derive(Clone)]
+    struct KeyedHashBuilder(u64, u64);
+
+    impl BuildHasher for KeyedHashBuilder {
+        type Hasher = SipHasher;
+
+        fn build_hasher(&self) -> Self::Hasher {
+            SipHasher::new_with_keys(self.0, self.1)
+        }
+    }
+
+    #[test]
+    fn heterogeneous_rings() {
+        let mut ring1: HashRing<&str> = HashRing::new();
+        let mut ring2 = HashRing::with_hasher(KeyedHashBuilder(1, 2));
+
+        for node in ["a", "b", "c"] {
+            ring1.add_node(node).unwrap();
+        }
+
+        for node in ["x", "y"] {
+            ring2.add_node(node).unwrap();
+        }
+
+        let expected1 = *ring1.get_by_hash(&b"foo".as_slice()).unwrap().data();
+        let expected2 = *ring2.get_by_hash(&b"foo".as_slice()).unwrap().data();
+
+        let rings: Vec<Box<dyn DynRing<&str>>> = vec![Box::new(ring1), Box::new(ring2)];
+
+        assert_eq!(rings[0].len(), 3);
+        assert_eq!(rings[1].len(), 2);
+
+        let keys = rings[0].keys();
+        assert_eq!(keys.len(), 3);
+        assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+
+        assert_eq!(rings[0].get_owned(b"foo").unwrap(), expected1);
+        assert_eq!(rings[1].get_owned(b"foo").unwrap(), expected2);
+    }
+}
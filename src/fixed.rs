@@ -0,0 +1,97 @@
+use {
+    super::{DefaultHashBuilder, Error, Node, RingHasher},
+    core::hash::Hash,
+};
+
+/// An array-backed hash ring with a fixed number of nodes, known at compile
+/// time. Unlike `HashRing`, it never allocates.
+#[derive(Clone)]
+pub struct FixedHashRing<T, S: RingHasher, const N: usize> {
+    hash_builder: S,
+    data: [Node<S::Key, T>; N],
+}
+
+impl<T: Hash, const N: usize> FixedHashRing<T, DefaultHashBuilder, N> {
+    /// Creates a `FixedHashRing` from `nodes`.
+    pub fn from_array(nodes: [T; N]) -> Self {
+        Self::from_array_with_hasher(nodes, DefaultHashBuilder)
+    }
+}
+
+impl<T, S, const N: usize> FixedHashRing<T, S, N>
+where
+    T: Hash,
+    S: RingHasher,
+{
+    /// Creates a `FixedHashRing` from `nodes`, using `hash_builder` to hash
+    /// them.
+    pub fn from_array_with_hasher(nodes: [T; N], hash_builder: S) -> Self {
+        let mut data = nodes.map(|node| Node::new(hash_builder.get_key(&node), node));
+        data.sort_by(|a, b| a.key.cmp(&b.key));
+
+        FixedHashRing { hash_builder, data }
+    }
+
+    /// Get the number of nodes in the hash ring.
+    #[inline]
+    pub const fn len(&self) -> usize {
+        N
+    }
+
+    /// Returns true if the ring has no elements.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Returns the data of the node containing `key`, or an error if the hash
+    /// ring is empty.
+    #[inline]
+    pub fn get_by_hash<U: Hash>(&self, key: &U) -> Result<&T, Error> {
+        self.get_by_key(&self.hash_builder.get_key(key))
+    }
+
+    /// Returns the data of the node containing `key`, or an error if the hash
+    /// ring is empty.
+    pub fn get_by_key(&self, key: &S::Key) -> Result<&T, Error> {
+        if N == 0 {
+            return Err(Error::NodeNotFound);
+        }
+
+        let index = match self.data.binary_search_by(|node| node.key.cmp(key)) {
+            Err(index) => index,
+            Ok(index) => index,
+        };
+
+        Ok(&self.data[if index == N { 0 } else { index }].data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::HashRing};
+
+    #[test]
+    fn fixed_routing() {
+        let nodes = ["a", "b", "c", "d"];
+        let fixed = FixedHashRing::from_array(nodes);
+
+        let mut ring = HashRing::new();
+        for node in nodes {
+            ring.add_node(node).unwrap();
+        }
+
+        assert_eq!(fixed.len(), 4);
+
+        for key in 0..1000 {
+            assert_eq!(
+                fixed.get_by_hash(&key).unwrap(),
+                ring.get_by_hash(&key).unwrap().data()
+            );
+        }
+
+        let empty: FixedHashRing<&str, _, 0> = FixedHashRing::from_array([]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.get_by_hash(&"foo"), Err(Error::NodeNotFound));
+    }
+}
@@ -0,0 +1,115 @@
+use {
+    super::{DefaultHashBuilder, Error, RingHasher},
+    core::hash::Hash,
+};
+
+/// Jump consistent hashing (Lamping & Veach), mapping keys to buckets
+/// numbered `0..n` without storing any nodes. Keys are perfectly balanced
+/// across buckets, but buckets can only be added or removed at the end, by
+/// changing the number of buckets.
+#[derive(Clone)]
+pub struct JumpHashRing<S = DefaultHashBuilder> {
+    hash_builder: S,
+    buckets: u32,
+}
+
+impl JumpHashRing {
+    /// Creates a `JumpHashRing` with `buckets` buckets.
+    pub fn new(buckets: u32) -> Self {
+        Self::with_hasher(buckets, DefaultHashBuilder)
+    }
+}
+
+impl<S: RingHasher<Key = u64>> JumpHashRing<S> {
+    /// Creates a `JumpHashRing` with `buckets` buckets, which will use the
+    /// given hash builder.
+    pub fn with_hasher(buckets: u32, hash_builder: S) -> Self {
+        JumpHashRing {
+            hash_builder,
+            buckets,
+        }
+    }
+
+    /// Get the number of buckets.
+    #[inline]
+    pub fn buckets(&self) -> u32 {
+        self.buckets
+    }
+
+    /// Sets the number of buckets. When growing from `n` to `m` buckets, only
+    /// the keys moving to the new buckets change bucket, i.e. a fraction of
+    /// `(m - n) / m` of them.
+    #[inline]
+    pub fn set_buckets(&mut self, buckets: u32) {
+        self.buckets = buckets;
+    }
+
+    /// Returns the bucket containing `key`, or an error if there are no
+    /// buckets.
+    #[inline]
+    pub fn bucket<U: Hash>(&self, key: &U) -> Result<u32, Error> {
+        if self.buckets == 0 {
+            return Err(Error::NodeNotFound);
+        }
+
+        Ok(jump(self.hash_builder.get_key(key), self.buckets))
+    }
+}
+
+/// Internal function implementing jump consistent hashing.
+fn jump(mut key: u64, buckets: u32) -> u32 {
+    let mut bucket = -1i64;
+    let mut next = 0i64;
+
+    while next < buckets as i64 {
+        bucket = next;
+        key = key.wrapping_mul(2_862_933_555_777_941_757).wrapping_add(1);
+        next = ((bucket + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+
+    bucket as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jump_hashing() {
+        assert_eq!(jump(0, 1), 0);
+        assert_eq!(jump(0, 1000), 0);
+        assert!((0..1000).all(|key| jump(key, 1) == 0));
+
+        let mut ring = JumpHashRing::new(0);
+        assert_eq!(ring.bucket(&"foo"), Err(Error::NodeNotFound));
+
+        ring.set_buckets(10);
+        assert_eq!(ring.buckets(), 10);
+
+        let mut counts = [0; 10];
+        for key in 0..10_000 {
+            counts[ring.bucket(&key).unwrap() as usize] += 1;
+        }
+        assert!(counts.iter().all(|&count| (900..1100).contains(&count)));
+
+        // Growing only moves keys to the new bucket.
+        let keys = 0..10_000u32;
+        let before = keys
+            .clone()
+            .map(|key| ring.bucket(&key).unwrap())
+            .collect::<Vec<_>>();
+
+        ring.set_buckets(11);
+
+        let mut moved = 0;
+        for (key, before) in keys.zip(before) {
+            let after = ring.bucket(&key).unwrap();
+
+            if after != before {
+                assert_eq!(after, 10);
+                moved += 1;
+            }
+        }
+        assert!((800..1000).contains(&moved));
+    }
+}
@@ -81,31 +81,109 @@
 //! }
 //! ```
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Read},
+};
+
 use {
-    range::KeyRange,
+    alloc::{borrow::Cow, string::String, sync::Arc, vec::Vec},
+    core::{
+        fmt::{self, Debug, Write},
+        hash::{BuildHasher, Hash, Hasher},
+        ops::Range,
+        sync::atomic::{AtomicUsize, Ordering},
+    },
+    range::{KeyRange, RingKey},
     siphasher::sip::SipHasher,
-    std::hash::{BuildHasher, Hash, Hasher},
 };
 
+pub mod migration;
 pub mod range;
 
-#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+mod composite;
+mod dyn_ring;
+mod fixed;
+mod jump;
+mod non_empty;
+mod quorum;
+mod rendezvous;
+mod router;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "shared")]
+mod shared;
+#[cfg(feature = "futures")]
+mod stream;
+
+#[cfg(feature = "shared")]
+pub use shared::*;
+pub use {
+    composite::*,
+    dyn_ring::*,
+    fixed::*,
+    jump::*,
+    non_empty::*,
+    quorum::*,
+    rendezvous::*,
+    router::*,
+};
+
+#[derive(Debug, PartialEq, Eq)]
 pub enum Error {
-    #[error("Duplicate node")]
     DuplicateNode,
-
-    #[error("Node not found")]
     NodeNotFound,
+    KeyCollision,
+    InsufficientReplicas { available: usize, required: usize },
+    KeyMismatch,
 }
 
-pub trait RingHasher: BuildHasher + Clone {
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DuplicateNode => f.write_str("Duplicate node"),
+            Error::NodeNotFound => f.write_str("Node not found"),
+            Error::KeyCollision => f.write_str("Key collision with a different node"),
+            Error::InsufficientReplicas {
+                available,
+                required,
+            } => write!(
+                f,
+                "Insufficient replicas: {available} available, {required} required"
+            ),
+            Error::KeyMismatch => f.write_str("Key mismatch"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+pub trait RingHasher: Clone {
     type Key: Clone + PartialEq + Eq + PartialOrd + Ord;
 
     fn get_key<T: Hash>(&self, input: T) -> Self::Key;
 }
 
+/// Any standard `BuildHasher`, such as `DefaultHashBuilder` or
+/// `std::collections::hash_map::RandomState`, can be used as a `RingHasher`,
+/// producing 64-bit keys.
+impl<B: BuildHasher + Clone> RingHasher for B {
+    type Key = u64;
+
+    fn get_key<T: Hash>(&self, input: T) -> Self::Key {
+        self.hash_one(input)
+    }
+}
+
 /// Default hash builder. Based on `SipHasher`, which produces 64-bit hashes.
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct DefaultHashBuilder;
 
 impl BuildHasher for DefaultHashBuilder {
@@ -116,17 +194,177 @@ impl BuildHasher for DefaultHashBuilder {
     }
 }
 
-impl RingHasher for DefaultHashBuilder {
+/// Hash builder based on `SipHasher` keyed with two secret seeds, which
+/// produces 64-bit hashes. Unlike `DefaultHashBuilder`, the placement of keys
+/// can't be predicted without the seeds, so keys can't be crafted to all land
+/// on the same node. The seeds must stay the same for the lifetime of a ring.
+#[derive(Clone)]
+pub struct SeededHashBuilder {
+    k0: u64,
+    k1: u64,
+}
+
+impl SeededHashBuilder {
+    /// Creates a `SeededHashBuilder` using the seeds `k0` and `k1`.
+    pub fn new(k0: u64, k1: u64) -> Self {
+        SeededHashBuilder { k0, k1 }
+    }
+
+    /// Creates a `SeededHashBuilder` using random seeds, taken from the
+    /// per-process randomness of `std::collections::hash_map::RandomState`.
+    #[cfg(feature = "std")]
+    pub fn from_entropy() -> Self {
+        let state = std::collections::hash_map::RandomState::new();
+
+        SeededHashBuilder {
+            k0: state.hash_one(0u8),
+            k1: state.hash_one(1u8),
+        }
+    }
+}
+
+impl BuildHasher for SeededHashBuilder {
+    type Hasher = SipHasher;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        SipHasher::new_with_keys(self.k0, self.k1)
+    }
+}
+
+/// Mixes a 64-bit hash using Fibonacci hashing (multiplying by `2^64 / φ`).
+pub fn fibonacci_mix(hash: u64) -> u64 {
+    hash.wrapping_mul(0x9e37_79b9_7f4a_7c15)
+}
+
+/// Hash builder applying a `mixer` function to the keys produced by another
+/// hash builder. Since the ring uses the same hash builder for nodes and lookup
+/// keys, the mixer is applied consistently to both. This helps spread out
+/// structured inputs when the underlying hash doesn't.
+#[derive(Clone)]
+pub struct MixedHashBuilder<S = DefaultHashBuilder> {
+    inner: S,
+    mixer: fn(u64) -> u64,
+}
+
+impl<S> MixedHashBuilder<S> {
+    /// Creates a `MixedHashBuilder` applying `mixer` to the keys of `inner`.
+    pub fn new(inner: S, mixer: fn(u64) -> u64) -> Self {
+        MixedHashBuilder { inner, mixer }
+    }
+}
+
+impl<S: RingHasher<Key = u64>> RingHasher for MixedHashBuilder<S> {
     type Key = u64;
 
-    fn get_key<T: Hash>(&self, input: T) -> Self::Key
-    where
-        T: Hash,
-    {
-        let mut hasher = self.build_hasher();
+    fn get_key<T: Hash>(&self, input: T) -> Self::Key {
+        (self.mixer)(self.inner.get_key(input))
+    }
+}
+
+/// Hash builder based on xxHash (XXH64), which produces 64-bit hashes. Much
+/// faster than `DefaultHashBuilder`, but not resistant to hash flooding, so it
+/// is best suited for trusted keys.
+#[cfg(feature = "xxhash")]
+#[derive(Clone, Default)]
+pub struct XxHashBuilder {
+    seed: u64,
+}
+
+#[cfg(feature = "xxhash")]
+impl XxHashBuilder {
+    /// Creates an `XxHashBuilder` using `seed`.
+    pub fn with_seed(seed: u64) -> Self {
+        XxHashBuilder { seed }
+    }
+}
+
+#[cfg(feature = "xxhash")]
+impl BuildHasher for XxHashBuilder {
+    type Hasher = twox_hash::XxHash64;
+
+    fn build_hasher(&self) -> Self::Hasher {
+        twox_hash::XxHash64::with_seed(self.seed)
+    }
+}
+
+/// Hash builder producing 32-bit keys using the standard CRC32 (IEEE), for
+/// interoperability with systems sharding on the CRC32 of their keys. Wrap keys
+/// in `RawBytes` to hash just their bytes, like such systems do.
+#[cfg(feature = "crc32")]
+#[derive(Clone, Default)]
+pub struct Crc32HashBuilder;
+
+#[cfg(feature = "crc32")]
+impl RingHasher for Crc32HashBuilder {
+    type Key = u32;
+
+    fn get_key<T: Hash>(&self, input: T) -> Self::Key {
+        let mut hasher = crc32fast::Hasher::new();
+        input.hash(&mut hasher);
+        hasher.finalize()
+    }
+}
+
+/// Hash builder producing 128-bit keys from the first 16 bytes of a BLAKE3
+/// digest. The larger keyspace makes key collisions between nodes, and thus
+/// `Error::DuplicateNode`, practically impossible even with many thousands of
+/// virtual nodes.
+#[cfg(feature = "blake3")]
+#[derive(Clone, Default)]
+pub struct Blake3HashBuilder;
+
+#[cfg(feature = "blake3")]
+impl RingHasher for Blake3HashBuilder {
+    type Key = u128;
+
+    fn get_key<T: Hash>(&self, input: T) -> Self::Key {
+        let mut hasher = Blake3Hasher(blake3::Hasher::new());
         input.hash(&mut hasher);
-        hasher.finish()
+
+        let digest = hasher.0.finalize();
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(&digest.as_bytes()[..16]);
+
+        u128::from_be_bytes(bytes)
+    }
+}
+
+/// Adapter feeding `Hash` implementations into a BLAKE3 hasher.
+#[cfg(feature = "blake3")]
+struct Blake3Hasher(blake3::Hasher);
+
+#[cfg(feature = "blake3")]
+impl Hasher for Blake3Hasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
     }
+
+    fn finish(&self) -> u64 {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&self.0.finalize().as_bytes()[..8]);
+        u64::from_be_bytes(bytes)
+    }
+}
+
+/// Byte string hashing as just its bytes. The `Hash` implementations of
+/// `[u8]` and `str` add a length prefix or terminator to their bytes, which
+/// makes the keys they produce differ from hashing the raw bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RawBytes<'a>(pub &'a [u8]);
+
+impl Hash for RawBytes<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write(self.0);
+    }
+}
+
+/// Internal function hashing `value` into a deterministic source of randomness.
+/// Uses different keys than `DefaultHashBuilder`, so the result is independent
+/// from ring positions.
+fn random_u64<U: Hash>(value: &U) -> u64 {
+    let mut hasher = SipHasher::new_with_keys(0x6861_7368_7269_6e67, 0x7261_6e64_6f6d_6e73);
+    value.hash(&mut hasher);
+    hasher.finish()
 }
 
 /// Node is an internal struct used to encapsulate the nodes that will be added
@@ -143,10 +381,75 @@ impl<K, T> Node<K, T> {
     }
 }
 
-#[derive(Clone)]
+/// WeightedPoint is an internal struct identifying, by its key, one of the
+/// points of a node added with `HashRing::add_weighted_node()`. Points are kept
+/// in a table beside the nodes, so rings without weighted nodes don't pay for
+/// them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct WeightedPoint<K> {
+    key: K,
+    point: u32,
+    total: u32,
+}
+
+/// A lookup performed on a `HashRing`, as passed to its observer. See
+/// `HashRing::with_observer()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupEvent<K> {
+    /// The hashed lookup key.
+    pub key: K,
+    /// The index of the node the key was routed to.
+    pub index: usize,
+    /// The number of nodes skipped past the key's owner, e.g. by fallback
+    /// lookups.
+    pub hops: usize,
+}
+
+/// Hook invoked on lookups. See `HashRing::with_observer()`.
+pub type LookupObserver<K> = Arc<dyn Fn(LookupEvent<K>) + Send + Sync>;
+
 pub struct HashRing<T, S: RingHasher = DefaultHashBuilder> {
     hash_builder: S,
     data: Vec<Node<S::Key, T>>,
+    // Sorted by key.
+    weights: Vec<WeightedPoint<S::Key>>,
+    // Number of nodes added with `add_weighted_node()`, i.e. of their first
+    // points in `weights`.
+    weighted_nodes: usize,
+    observer: Option<LookupObserver<S::Key>>,
+    version: u64,
+    // Tells rings apart, since different rings may have the same version.
+    id: usize,
+}
+
+/// The longest key accepted by `HashRing::route_reader()`, in bytes. Longer
+/// length prefixes are rejected, rather than allocating whatever a corrupt
+/// stream asks for.
+#[cfg(feature = "std")]
+pub const MAX_STREAMED_KEY_LEN: usize = 64 * 1024;
+
+/// Source of the identifiers of rings. See `CachingRouter`.
+static NEXT_RING_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Internal function returning a new ring identifier.
+fn next_ring_id() -> usize {
+    NEXT_RING_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Clones get their own identifier, since they can change independently of the
+/// original ring.
+impl<T: Clone, S: RingHasher> Clone for HashRing<T, S> {
+    fn clone(&self) -> Self {
+        HashRing {
+            hash_builder: self.hash_builder.clone(),
+            data: self.data.clone(),
+            weights: self.weights.clone(),
+            weighted_nodes: self.weighted_nodes,
+            observer: self.observer.clone(),
+            version: self.version,
+            id: next_ring_id(),
+        }
+    }
 }
 
 impl<T> Default for HashRing<T> {
@@ -154,10 +457,65 @@ impl<T> Default for HashRing<T> {
         HashRing {
             hash_builder: DefaultHashBuilder,
             data: Vec::new(),
+            weights: Vec::new(),
+            weighted_nodes: 0,
+            observer: None,
+            version: 0,
+            id: next_ring_id(),
         }
     }
 }
 
+/// Compares the nodes of two rings, along with their keys and weights. The hash
+/// builders, observers and versions are not compared.
+impl<T, S> PartialEq for HashRing<T, S>
+where
+    T: PartialEq,
+    S: RingHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data && self.weights == other.weights
+    }
+}
+
+impl<T: Eq, S: RingHasher> Eq for HashRing<T, S> {}
+
+/// Builds a ring from `nodes`, sorting once at the end. Duplicate nodes, as
+/// well as nodes whose key collides with an earlier node, are ignored. See
+/// `Extend`.
+impl<T: Hash> FromIterator<T> for HashRing<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(nodes: I) -> Self {
+        let mut ring = HashRing::new();
+        ring.extend(nodes);
+        ring
+    }
+}
+
+/// Adds `nodes` to the ring, sorting once at the end. Nodes the ring already
+/// contains, as well as repeated nodes, are ignored, keeping the first
+/// occurrence. Like `add_node()`, nodes are compared by key, so a node whose
+/// key collides with a different node is silently dropped as well. Use
+/// `add_node_checked()` to detect collisions.
+impl<T, S> Extend<T> for HashRing<T, S>
+where
+    T: Hash,
+    S: RingHasher,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, nodes: I) {
+        let mut added = nodes
+            .into_iter()
+            .map(|node| Node::new(self.key(&node), node))
+            .collect::<Vec<_>>();
+
+        added.sort_by(|a, b| a.key.cmp(&b.key));
+        added.dedup_by(|a, b| a.key == b.key);
+        added.retain(|node| !self.contains_node(&node.data));
+
+        self.data.append(&mut added);
+        self.sort();
+    }
+}
+
 /// Hash Ring
 ///
 /// A hash ring that provides consistent hashing for nodes that are added to it.
@@ -166,6 +524,35 @@ impl<T> HashRing<T> {
     pub fn new() -> Self {
         Default::default()
     }
+
+    /// Creates an empty `HashRing` with space for at least `capacity` nodes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        HashRing {
+            hash_builder: DefaultHashBuilder,
+            data: Vec::with_capacity(capacity),
+            weights: Vec::new(),
+            weighted_nodes: 0,
+            observer: None,
+            version: 0,
+            id: next_ring_id(),
+        }
+    }
+}
+
+impl<T> HashRing<T, MixedHashBuilder> {
+    /// Creates an empty `HashRing` using the default hash builder, with `mixer`
+    /// applied to all keys.
+    pub fn with_mixer(mixer: fn(u64) -> u64) -> Self {
+        HashRing {
+            hash_builder: MixedHashBuilder::new(DefaultHashBuilder, mixer),
+            data: Vec::new(),
+            weights: Vec::new(),
+            weighted_nodes: 0,
+            observer: None,
+            version: 0,
+            id: next_ring_id(),
+        }
+    }
 }
 
 impl<T, S> HashRing<T, S>
@@ -178,6 +565,41 @@ where
         HashRing {
             hash_builder,
             data: Vec::new(),
+            weights: Vec::new(),
+            weighted_nodes: 0,
+            observer: None,
+            version: 0,
+            id: next_ring_id(),
+        }
+    }
+
+    /// Creates an empty `HashRing` with space for at least `capacity` nodes,
+    /// which will use the given hash builder.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        HashRing {
+            hash_builder,
+            data: Vec::with_capacity(capacity),
+            weights: Vec::new(),
+            weighted_nodes: 0,
+            observer: None,
+            version: 0,
+            id: next_ring_id(),
+        }
+    }
+
+    /// Sets a hook invoked with a `LookupEvent` on every `get_by_hash()`
+    /// lookup, on each key of batched `owners_of()` lookups, as well as on
+    /// fallback lookups such as `get_excluding()` and `get_tiered()`. Useful
+    /// for feeding metrics or tracing.
+    ///
+    /// Lookups by hashed key through `get_stable()`, `get_fair()`, the routers
+    /// and `NonEmptyHashRing::get_by_hash()` are observed too, with the index
+    /// of the node they return. Lookups by raw key, such as `get_by_key()`, are
+    /// not.
+    pub fn with_observer(self, observer: LookupObserver<S::Key>) -> Self {
+        HashRing {
+            observer: Some(observer),
+            ..self
         }
     }
 
@@ -187,20 +609,115 @@ where
         self.data.len()
     }
 
+    /// Returns the number of nodes added to the hash ring, counting the points
+    /// of each node added with `add_weighted_node()` once, whereas `len()`
+    /// counts every point. Unlike `physical_node_count()`, this runs in
+    /// constant time, but nodes added several times with `add_node()` are
+    /// counted each time.
+    #[inline]
+    pub fn physical_len(&self) -> usize {
+        self.data.len() - self.weights.len() + self.weighted_nodes
+    }
+
+    /// Returns the number of distinct nodes in the hash ring. Unlike `len()`,
+    /// nodes placed on the ring several times, such as virtual nodes sharing
+    /// their data, or nodes added with `add_weighted_node()`, are counted once.
+    #[cfg(feature = "std")]
+    pub fn physical_node_count(&self) -> usize
+    where
+        T: Eq,
+    {
+        self.data
+            .iter()
+            .map(|node| &node.data)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
     /// Returns true if the ring has no elements.
     #[inline]
     pub fn is_empty(&self) -> bool {
         self.data.len() == 0
     }
 
+    /// Removes all nodes from the hash ring, keeping its allocated capacity for
+    /// adding nodes again.
+    pub fn clear(&mut self) {
+        self.data.clear();
+        self.weights.clear();
+        self.weighted_nodes = 0;
+        self.version += 1;
+    }
+
+    /// Shrinks the capacity of the hash ring as much as possible, e.g. to free
+    /// memory after removing many nodes.
+    pub fn shrink_to_fit(&mut self) {
+        self.data.shrink_to_fit();
+        self.weights.shrink_to_fit();
+    }
+
+    /// Returns a counter that is incremented whenever the ring's nodes change,
+    /// which lets callers tell whether state derived from the ring is stale.
+    #[inline]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
     /// Hashes `data` and returns its key into the hash ring.
     #[inline]
     pub fn key<U: Hash>(&self, data: &U) -> S::Key {
         self.hash_builder.get_key(data)
     }
 
+    /// Returns the keys of all nodes, i.e. the partition boundaries, in ring
+    /// (ascending) order. Rings built from the same
+    /// nodes with the same hash builder have equal fingerprints, regardless of
+    /// the order the nodes were added in.
+    pub fn fingerprint(&self) -> Vec<S::Key> {
+        self.data.iter().map(|node| node.key.clone()).collect()
+    }
+
+    /// Returns true if every node of this ring is also in `other`. Nodes are
+    /// compared by key, walking both (sorted) rings once.
+    pub fn is_subset_of(&self, other: &HashRing<T, S>) -> bool {
+        let mut others = other.data.iter().map(|node| &node.key).peekable();
+
+        self.data.iter().all(|node| {
+            while others.next_if(|key| *key < &node.key).is_some() {}
+            others.next_if_eq(&&node.key).is_some()
+        })
+    }
+
+    /// Re-hashes every node's data and returns the indices of nodes whose
+    /// stored key no longer matches, e.g. for a ring that was restored from a
+    /// snapshot taken with a different hash builder.
+    pub fn verify_keys(&self) -> Result<(), Vec<usize>> {
+        let mismatched = self
+            .data
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| {
+                let key = match self.weighted_point(&node.key) {
+                    Some(weighted) => self.key(&(&node.data, weighted.point)),
+                    None => self.key(&node.data),
+                };
+
+                key != node.key
+            })
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+
+        if mismatched.is_empty() {
+            Ok(())
+        } else {
+            Err(mismatched)
+        }
+    }
+
     /// Adds `node` to the hash ring. Returns the new node's index, or an error
-    /// if the hash ring already contains the node.
+    /// if the hash ring already contains the node. A different node with the
+    /// same key is reported as a duplicate as well, see `add_node_checked()` to
+    /// tell them apart.
     pub fn add_node(&mut self, node: T) -> Result<usize, Error> {
         let key = self.key(&node);
 
@@ -209,501 +726,3938 @@ where
         };
 
         self.data.insert(index, Node::new(key, node));
+        self.version += 1;
 
         Ok(index)
     }
 
-    /// Similar to `add_node()`, but doesn't check for duplicate nodes, and
-    /// requires to be sorted after all of the nodes are added.
-    pub fn add_node_unchecked(&mut self, node: T) {
+    /// Similar to `add_node()`, but compares `node` with the node already
+    /// stored at its key, returning `Error::DuplicateNode` only if they are
+    /// equal, and `Error::KeyCollision` if they differ.
+    pub fn add_node_checked(&mut self, node: T) -> Result<usize, Error>
+    where
+        T: PartialEq,
+    {
         let key = self.key(&node);
-        self.data.push(Node::new(key, node));
-    }
 
-    /// Sorts the ring. This is required after adding nodes with
-    /// `add_node_unchecked()`.
-    pub fn sort(&mut self) {
-        self.data.sort_by(|a, b| a.key.cmp(&b.key))
+        let index = match self.find_node(&key) {
+            Err(index) => index,
+            Ok(index) if self.data[index].data == node => return Err(Error::DuplicateNode),
+            Ok(_) => return Err(Error::KeyCollision),
+        };
+
+        self.data.insert(index, Node::new(key, node));
+        self.version += 1;
+
+        Ok(index)
     }
 
-    /// Removes `node` from the hash ring. Returns an `Error` if the hash ring
-    /// does not contain the `node`.
-    pub fn remove_node(&mut self, node: &T) -> Result<(), Error> {
-        let key = self.key(node);
+    /// Adds `node` to the hash ring. Returns the new node's index, along with
+    /// the range of keys it took over from its clockwise successor, or an error
+    /// if the hash ring already contains the node. For the first node, the
+    /// range covers the whole ring.
+    pub fn add_node_with_migration(&mut self, node: T) -> Result<(usize, KeyRange<S::Key>), Error>
+    where
+        S::Key: RingKey,
+    {
+        let index = self.add_node(node)?;
+        let range = NodeRef { ring: self, index }.owned_range();
 
-        self.find_node(&key)
-            .map(|idx| {
-                self.data.remove(idx);
-            })
-            .map_err(|_| Error::NodeNotFound)
+        Ok((index, range))
     }
 
-    /// Returns the `NodeRef` for the node containing `key`, or an error if the
-    /// hash ring is empty.
-    #[inline]
-    pub fn get_by_hash<U: Hash>(&self, key: &U) -> Result<NodeRef<'_, T, S>, Error> {
-        self.get_by_key(&self.key(key))
+    /// Removes `node` from the hash ring, along with all of its points if it
+    /// was added with `add_weighted_node()`. Returns the merged ranges of keys
+    /// taken over by the clockwise successors of the removed points, or an
+    /// error if the hash ring does not contain the `node`.
+    pub fn remove_node_with_migration(&mut self, node: &T) -> Result<Vec<KeyRange<S::Key>>, Error>
+    where
+        S::Key: RingKey,
+    {
+        let ranges = self
+            .point_indices(node)?
+            .into_iter()
+            .map(|index| NodeRef { ring: self, index }.owned_range())
+            .collect::<Vec<_>>();
+
+        self.remove_node(node)?;
+
+        Ok(range::merge_ranges(ranges).collect())
     }
 
-    /// Returns the `NodeRef` for the node containing `key`, or an error if the
-    /// hash ring is empty.
-    #[inline]
-    pub fn get_by_key(&self, key: &S::Key) -> Result<NodeRef<'_, T, S>, Error> {
-        if self.data.is_empty() {
-            return Err(Error::NodeNotFound);
+    /// Adds `node` to the hash ring with `weight` points, placed by hashing
+    /// `(node, point)` for each point, so the node owns a share of the keyspace
+    /// proportional to its weight. Returns an error if the hash ring already
+    /// contains the node, or `Error::KeyCollision` if one of the points has the
+    /// same key as another node, in which case nothing is added. A weight of
+    /// zero adds nothing.
+    pub fn add_weighted_node(&mut self, node: T, weight: u32) -> Result<(), Error>
+    where
+        T: Clone + PartialEq,
+    {
+        if self.weight_of(&node).is_some() {
+            return Err(Error::DuplicateNode);
         }
 
-        let index = match self.find_node(key) {
-            Err(index) => index,
-            Ok(index) => index,
-        };
+        let mut keys = (0..weight)
+            .map(|point| (self.key(&(&node, point)), point))
+            .collect::<Vec<_>>();
+        keys.sort_by(|a, b| a.0.cmp(&b.0));
 
-        let index = if index == self.data.len() { 0 } else { index };
+        if keys.windows(2).any(|pair| pair[0].0 == pair[1].0)
+            || keys.iter().any(|(key, _)| self.find_node(key).is_ok())
+        {
+            return Err(Error::KeyCollision);
+        }
 
-        self.get_by_index(index)
-    }
+        for (key, point) in keys {
+            // Safe to ignore, since colliding keys were rejected above.
+            let (Err(index) | Ok(index)) = self.find_node(&key);
+            self.data
+                .insert(index, Node::new(key.clone(), node.clone()));
 
-    /// Returns the `NodeRef` by node index within the hash ring, or an error if
-    /// the hash ring is empty.
-    #[inline]
-    pub fn get_by_index(&self, index: usize) -> Result<NodeRef<'_, T, S>, Error> {
-        if index < self.len() {
-            Ok(NodeRef { ring: self, index })
-        } else {
-            Err(Error::NodeNotFound)
+            let (Err(index) | Ok(index)) = self.find_weighted_point(&key);
+            self.weights.insert(index, WeightedPoint {
+                key,
+                point,
+                total: weight,
+            });
         }
-    }
 
-    /// Searches the ring for `node` and returns its `NodeRef`, or an error if
-    /// the node is not found.
-    #[inline]
-    pub fn node(&self, node: &T) -> Result<NodeRef<'_, T, S>, Error> {
-        if self.data.is_empty() {
-            return Err(Error::NodeNotFound);
+        if weight > 0 {
+            self.weighted_nodes += 1;
         }
 
+        self.version += 1;
+
+        Ok(())
+    }
+
+    /// Returns the weight `node` was added with, `1` if it was added with
+    /// `add_node()`, or `None` if the hash ring does not contain the node.
+    pub fn weight_of(&self, node: &T) -> Option<u32>
+    where
+        T: PartialEq,
+    {
         let key = self.key(node);
 
-        let Ok(index) = self.find_node(&key) else {
-            return Err(Error::NodeNotFound);
-        };
+        if let Ok(index) = self.find_node(&key) {
+            if self.data[index].data == *node && self.weighted_point(&key).is_none() {
+                return Some(1);
+            }
+        }
 
-        self.get_by_index(index)
-    }
+        let key = self.key(&(node, 0u32));
+        let index = self.find_node(&key).ok()?;
 
-    pub fn iter(&self, start: impl Into<Option<S::Key>>) -> Iter<'_, T, S> {
-        let start_node = if let Some(start_key) = start.into() {
-            self.get_by_key(&start_key)
-        } else {
-            self.get_by_index(0)
-        };
+        if self.data[index].data != *node {
+            return None;
+        }
 
-        start_node.map(Iter::new).unwrap_or(Iter::empty())
+        self.weighted_point(&key).map(|weighted| weighted.total)
     }
 
-    /// Internal method for traversing the hash ring.
-    #[inline]
-    fn find_node(&self, key: &S::Key) -> Result<usize, usize> {
-        self.data.binary_search_by(|node| node.key.cmp(key))
-    }
+    /// Moves points between nodes added with `add_weighted_node()` to even out
+    /// their `observed_load`, assuming each node's load is proportional to its
+    /// weight. Each of up to `iterations` steps moves one point from the most
+    /// to the least loaded node, as long as that lowers the peak load; nodes
+    /// keep at least one point. Nodes missing from `observed_load`, or added
+    /// with `add_node()`, are left alone. Returns the ranges of keys changing
+    /// owner, along with their old and new owners.
+    ///
+    /// A node that can't take more points because one of them would collide
+    /// with another node keeps its previous weight.
+    #[cfg(feature = "std")]
+    pub fn equalize(
+        &mut self,
+        observed_load: &HashMap<T, u64>,
+        iterations: usize,
+    ) -> Vec<migration::OwnedChange<T, S::Key>>
+    where
+        T: Clone + Eq,
+        S::Key: RingKey,
+    {
+        // (node, original weight, load per point, new weight)
+        let mut nodes = observed_load
+            .iter()
+            .filter_map(|(node, &load)| {
+                let weight = self.weighted_point(&self.key(&(node, 0u32)))?.total;
+                Some((node, weight, load as f64 / weight as f64, weight))
+            })
+            .collect::<Vec<_>>();
+        // Ties are broken by position, rather than by the map's order.
+        nodes.sort_by_key(|(node, ..)| self.key(&(*node, 0u32)));
 
-    /// Internal method for wrapping node index within the hash ring.
-    #[inline]
-    fn wrap_index(&self, index: usize) -> usize {
-        index % self.data.len()
+        let load = |&(_, _, per_point, weight): &(&T, u32, f64, u32)| per_point * weight as f64;
+
+        for _ in 0..iterations {
+            let (Some(max), Some(min)) = (
+                (0..nodes.len()).max_by(|&a, &b| load(&nodes[a]).total_cmp(&load(&nodes[b]))),
+                (0..nodes.len()).min_by(|&a, &b| load(&nodes[a]).total_cmp(&load(&nodes[b]))),
+            ) else {
+                break;
+            };
+
+            if max == min
+                || nodes[max].3 == 1
+                || load(&nodes[min]) + nodes[min].2 >= load(&nodes[max])
+            {
+                break;
+            }
+
+            nodes[max].3 -= 1;
+            nodes[min].3 += 1;
+        }
+
+        let old = self.clone();
+
+        // Shrink nodes first, so that growing ones can take the keys they free.
+        nodes.sort_by_key(|&(_, weight, _, new_weight)| new_weight > weight);
+
+        for (node, weight, _, new_weight) in nodes {
+            if new_weight == weight {
+                continue;
+            }
+
+            // Safe to ignore, since the node was just found in the ring.
+            let _ = self.remove_node(node);
+
+            if self.add_weighted_node(node.clone(), new_weight).is_err() {
+                // Safe to ignore, since the node's previous points were its own.
+                let _ = self.add_weighted_node(node.clone(), weight);
+            }
+        }
+
+        migration::diff(&old, self)
+            .into_iter()
+            .map(|(range, from, to)| (range, from.cloned(), to.cloned()))
+            .collect()
     }
-}
 
-/// Reference to a hash ring node. Acts as an iterator (using `prev()` and
-/// `next()` methods), and provides additional node data like range and hash
-/// key.
-#[derive(Clone)]
-pub struct NodeRef<'a, T, S: RingHasher> {
-    ring: &'a HashRing<T, S>,
-    index: usize,
-}
+    /// Similar to `add_node()`, but doesn't check for duplicate nodes, and
+    /// requires to be sorted after all of the nodes are added.
+    pub fn add_node_unchecked(&mut self, node: T) {
+        let key = self.key(&node);
+        self.data.push(Node::new(key, node));
+        self.version += 1;
+    }
 
-impl<'a, T, S: RingHasher> std::fmt::Debug for NodeRef<'a, T, S> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("NodeRef")
-            .field("index", &self.index)
-            .finish_non_exhaustive()
+    /// Sorts the ring. This is required after adding nodes with
+    /// `add_node_unchecked()`.
+    pub fn sort(&mut self) {
+        self.data.sort_by(|a, b| a.key.cmp(&b.key));
+        self.version += 1;
     }
-}
 
-impl<'a, T, S> NodeRef<'a, T, S>
-where
-    T: Hash,
-    S: RingHasher,
-{
-    /// Returns the node's hash key.
-    #[inline]
-    pub fn key(&self) -> &S::Key {
-        &self.node().key
+    /// Removes `node` from the hash ring, along with all of its points if it
+    /// was added with `add_weighted_node()`. Returns an `Error` if the hash
+    /// ring does not contain the `node`.
+    pub fn remove_node(&mut self, node: &T) -> Result<(), Error> {
+        let mut indices = self.point_indices(node)?;
+        indices.sort_unstable_by(|a, b| b.cmp(a));
+
+        for index in indices {
+            let removed = self.data.remove(index);
+
+            if let Ok(index) = self.find_weighted_point(&removed.key) {
+                if self.weights.remove(index).point == 0 {
+                    self.weighted_nodes -= 1;
+                }
+            }
+        }
+
+        self.version += 1;
+
+        Ok(())
     }
 
-    /// Returns the node's data.
+    /// Replaces the data of `node` with `new_data`, keeping its position on the
+    /// ring, and that of all of its points if it was added with
+    /// `add_weighted_node()`. Returns an error if the hash ring does not
+    /// contain the `node`, or if `new_data` hashes to a different key, which
+    /// would move the node.
+    pub fn update_node(&mut self, node: &T, new_data: T) -> Result<(), Error>
+    where
+        T: Clone,
+    {
+        let indices = self.point_indices(node)?;
+
+        let moved = match self.weighted_point(&self.key(&(node, 0u32))) {
+            Some(weighted) => (0..weighted.total)
+                .any(|point| self.key(&(&new_data, point)) != self.key(&(node, point))),
+            None => self.key(&new_data) != self.key(node),
+        };
+
+        if moved {
+            return Err(Error::KeyMismatch);
+        }
+
+        for index in indices {
+            self.data[index].data = new_data.clone();
+        }
+
+        self.version += 1;
+
+        Ok(())
+    }
+
+    /// Retains only the nodes whose data satisfies `f`, removing the others in
+    /// a single pass. The ring stays sorted, so it doesn't need re-sorting.
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.data.retain(|node| f(&node.data));
+        self.prune_weights();
+        self.version += 1;
+    }
+
+    /// Removes all nodes with keys in `from..to` from the hash ring, and
+    /// returns their data in ring order. The range follows `KeyRange`
+    /// semantics, so it wraps around the end of the ring if `from >= to`,
+    /// and covers the whole ring if `from == to`.
+    pub fn drain_range(&mut self, from: &S::Key, to: &S::Key) -> Vec<T> {
+        let range = KeyRange::new(from.clone(), to.clone());
+
+        let (drained, kept): (Vec<_>, Vec<_>) = core::mem::take(&mut self.data)
+            .into_iter()
+            .partition(|node| range.contains(&node.key));
+
+        self.data = kept;
+        self.prune_weights();
+        self.version += 1;
+
+        drained.into_iter().map(|node| node.data).collect()
+    }
+
+    /// Removes nodes sharing their key with the node before them, and returns
+    /// their data. Such nodes own zero-width ranges, as keys routed to their
+    /// position go to the first node there, so they never serve traffic. The
+    /// first node at each position is always kept.
+    pub fn prune_zero_width(&mut self) -> Vec<T> {
+        let mut pruned = Vec::new();
+        let mut kept: Vec<Node<S::Key, T>> = Vec::with_capacity(self.data.len());
+
+        for node in core::mem::take(&mut self.data) {
+            if kept.last().is_some_and(|last| last.key == node.key) {
+                pruned.push(node.data);
+            } else {
+                kept.push(node);
+            }
+        }
+
+        self.data = kept;
+        self.prune_weights();
+        self.version += 1;
+
+        pruned
+    }
+
+    /// Returns the `NodeRef` for the node containing `key`, or an error if the
+    /// hash ring is empty.
     #[inline]
-    pub fn data(&self) -> &T {
-        &self.node().data
+    pub fn get_by_hash<U: Hash>(&self, key: &U) -> Result<NodeRef<'_, T, S>, Error> {
+        let key = self.key(key);
+        let node = self.get_by_key(&key)?;
+        self.observe(&key, node.index, 0);
+
+        Ok(node)
     }
 
-    /// Returns the previous node on the hash ring. If the hash ring contains
-    /// only one node, the returned reference will be for the same node.
+    /// Returns a clone of the data of the node containing `key`, or an error if
+    /// the hash ring is empty. Unlike `get_by_hash()`, the result doesn't
+    /// borrow the ring, so it can be modified right away.
     #[inline]
-    pub fn prev(&self) -> Self {
-        let ring = self.ring;
+    pub fn get_owned<U: Hash>(&self, key: &U) -> Result<T, Error>
+    where
+        T: Clone,
+    {
+        self.get_by_hash(key).map(|node| node.data().clone())
+    }
 
-        Self {
-            ring,
-            index: ring.wrap_index(ring.len() + self.index - 1),
+    /// Returns the `NodeRef` for the node containing `key`, or an error if the
+    /// hash ring is empty.
+    #[inline]
+    pub fn get_by_key(&self, key: &S::Key) -> Result<NodeRef<'_, T, S>, Error> {
+        if self.data.is_empty() {
+            return Err(Error::NodeNotFound);
         }
+
+        let index = match self.find_node(key) {
+            Err(index) => index,
+            Ok(index) => index,
+        };
+
+        let index = if index == self.data.len() { 0 } else { index };
+
+        self.get_by_index(index)
     }
 
-    /// Returns the next node on the hash ring. If the hash ring contains
-    /// only one node, the returned reference will be for the same node.
+    /// Returns the index of the node containing `key`, or an error if the hash
+    /// ring is empty.
     #[inline]
-    pub fn next(&self) -> Self {
-        let ring = self.ring;
+    pub fn owner_index<U: Hash>(&self, key: &U) -> Result<usize, Error> {
+        self.get_by_hash(key).map(|node| node.index)
+    }
 
-        Self {
-            ring,
-            index: ring.wrap_index(self.index + 1),
-        }
+    /// Builds the preference list of the `n` nodes following `key` clockwise,
+    /// and returns `pick` of them chosen at random (without replacement), in
+    /// preference order. Returns all `n` nodes if `pick >= n`, and an empty
+    /// vector if the hash ring is empty.
+    #[cfg(feature = "rand")]
+    pub fn sample_replicas<U: Hash, R: rand::Rng>(
+        &self,
+        key: &U,
+        n: usize,
+        pick: usize,
+        rng: &mut R,
+    ) -> Vec<NodeRef<'_, T, S>> {
+        let replicas = self.iter(self.key(key)).take(n).collect::<Vec<_>>();
+
+        let mut picked =
+            rand::seq::index::sample(rng, replicas.len(), pick.min(replicas.len())).into_vec();
+        picked.sort_unstable();
+
+        replicas
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| picked.binary_search(index).is_ok())
+            .map(|(_, node)| node)
+            .collect()
     }
 
-    /// Returns the nodes range on the hash ring.
+    /// Returns the index of the node containing `key`, along with the range of
+    /// keys routed to it, or an error if the hash ring is empty. The range
+    /// spans from just after the previous node's key up to and including the
+    /// owner's key, and contains `key`.
+    pub fn locate<U: Hash>(&self, key: &U) -> Result<(usize, KeyRange<S::Key>), Error>
+    where
+        S::Key: RingKey,
+    {
+        self.get_by_hash(key)
+            .map(|node| (node.index, node.owned_range()))
+    }
+
+    /// Returns the owner of `key` along with the range of keys it owns, or an
+    /// error if the hash ring is empty. Clients can verify the owner without
+    /// trusting the ring by checking that the range contains the key's hash.
+    pub fn ownership_proof<U: Hash>(&self, key: &U) -> Result<OwnershipProof<'_, T, S>, Error>
+    where
+        S::Key: RingKey,
+    {
+        self.get_by_hash(key).map(|node| {
+            let range = node.owned_range();
+            (node, range)
+        })
+    }
+
+    /// Returns the data of the node containing `key` as a `Cow`, or an error if
+    /// the hash ring is empty. The data is borrowed from the ring, and callers
+    /// that need to outlive the borrow can call `into_owned()` on the result.
     #[inline]
-    pub fn range(&self) -> KeyRange<S::Key> {
-        KeyRange {
-            start: self.key().clone(),
-            end: self.next().key().clone(),
+    pub fn get_cow<U: Hash>(&self, key: &U) -> Result<Cow<'_, T>, Error>
+    where
+        T: Clone,
+    {
+        self.owner_index(key)
+            .map(|index| Cow::Borrowed(&self.data[index].data))
+    }
+
+    /// Returns the `NodeRef` for the node at exactly `hint`, if there is one,
+    /// or otherwise the node containing `key`. This lets sessions stick to
+    /// a previously assigned node for as long as that node stays in the
+    /// ring.
+    pub fn get_sticky<U: Hash>(
+        &self,
+        key: &U,
+        hint: Option<&S::Key>,
+    ) -> Result<NodeRef<'_, T, S>, Error> {
+        if let Some(Ok(index)) = hint.map(|hint| self.find_node(hint)) {
+            return self.get_by_index(index);
+        }
+
+        self.get_by_hash(key)
+    }
+
+    /// Returns the `NodeRef` for the node containing `key`, except for about
+    /// `jitter_fraction` of `(key, epoch)` pairs, which go to the next node
+    /// instead. The choice is deterministic, so with e.g. time buckets as
+    /// epochs, a popular key's reads are spread over its owner and first
+    /// replica without every reader refreshing the same cache at once.
+    pub fn get_with_jitter<U: Hash>(
+        &self,
+        key: &U,
+        epoch: u64,
+        jitter_fraction: f64,
+    ) -> Result<NodeRef<'_, T, S>, Error> {
+        let node = self.get_by_hash(key)?;
+
+        if (random_u64(&(key, epoch)) as f64) < jitter_fraction * u64::MAX as f64 {
+            Ok(node.next())
+        } else {
+            Ok(node)
+        }
+    }
+
+    /// Returns the `NodeRef` for the first node clockwise from `key` whose key
+    /// is not in `excluded`, or an error if the hash ring is empty or all
+    /// nodes are excluded.
+    #[cfg(feature = "std")]
+    pub fn get_excluding<U: Hash>(
+        &self,
+        key: &U,
+        excluded: &HashSet<S::Key>,
+    ) -> Result<NodeRef<'_, T, S>, Error>
+    where
+        S::Key: Hash,
+    {
+        let key = self.key(key);
+        let (hops, node) = self
+            .iter(key.clone())
+            .enumerate()
+            .find(|(_, node)| !excluded.contains(node.key()))
+            .ok_or(Error::NodeNotFound)?;
+        self.observe(&key, node.index, hops);
+
+        Ok(node)
+    }
+
+    /// Returns the `NodeRef` for the first node clockwise from `key` in the
+    /// first zone of `tiers` that has any nodes, or an error if the hash ring
+    /// is empty. Zones are tried in order, falling back to the owner of `key`
+    /// if no node is in any of the `tiers`.
+    pub fn get_tiered<U, F>(
+        &self,
+        key: &U,
+        zone_of: F,
+        tiers: &[u8],
+    ) -> Result<NodeRef<'_, T, S>, Error>
+    where
+        U: Hash,
+        F: Fn(&T) -> u8,
+    {
+        let key = self.key(key);
+
+        let (hops, node) = tiers
+            .iter()
+            .find_map(|tier| {
+                self.iter(key.clone())
+                    .enumerate()
+                    .find(|(_, node)| zone_of(node.data()) == *tier)
+            })
+            .map_or_else(|| self.get_by_key(&key).map(|node| (0, node)), Ok)?;
+        self.observe(&key, node.index, hops);
+
+        Ok(node)
+    }
+
+    /// Returns up to `n` nodes with distinct data for `key`, walking clockwise
+    /// from its owner, or an error if the hash ring is empty. If the ring has
+    /// fewer than `n` distinct nodes, all of them are returned. This is the
+    /// replica set of `key`, skipping the extra points of weighted nodes.
+    pub fn get_n<U: Hash>(&self, key: &U, n: usize) -> Result<Vec<NodeRef<'_, T, S>>, Error>
+    where
+        T: PartialEq,
+    {
+        if self.data.is_empty() {
+            return Err(Error::NodeNotFound);
         }
+
+        Ok(self.distinct_nodes(key, n, |a, b| a == b))
     }
 
+    /// Returns an iterator over the preference list of `key`: its owner,
+    /// followed by the remaining nodes clockwise. Each node is yielded once,
+    /// and nothing is allocated. The iterator is empty if the hash ring is.
     #[inline]
-    fn node(&self) -> &Node<S::Key, T> {
-        // Safe unwrap, since the node ref would not exist otherwise.
-        self.ring.data.get(self.index).unwrap()
+    pub fn replicas_iter<U: Hash>(&self, key: &U) -> ReplicaIter<'_, T, S> {
+        self.iter(self.key(key))
     }
-}
 
-pub struct Iter<'a, T, S: RingHasher> {
-    start: usize,
-    next: Option<NodeRef<'a, T, S>>,
-}
+    /// Returns the preference list of the `rf` nodes following `key`
+    /// clockwise, rotated by an amount derived from `key`. Reads of different
+    /// keys thus start at different replicas, instead of all going to the
+    /// owner first, while the set of replicas stays the same.
+    pub fn read_preference<U: Hash>(&self, key: &U, rf: usize) -> Vec<NodeRef<'_, T, S>> {
+        let mut nodes = self.replicas_iter(key).take(rf).collect::<Vec<_>>();
 
-impl<'a, T, S> Iter<'a, T, S>
-where
-    T: Hash,
-    S: RingHasher,
-{
-    pub fn new(node: NodeRef<'a, T, S>) -> Self {
-        Self {
-            start: node.index,
-            next: Some(node),
+        if !nodes.is_empty() {
+            let shift = random_u64(key) % nodes.len() as u64;
+            nodes.rotate_left(shift as usize);
         }
+
+        nodes
     }
 
-    pub fn empty() -> Self {
-        Self {
-            start: 0,
-            next: None,
+    /// Returns the data of all nodes in failover order for `key`: the owner
+    /// first, followed by the remaining nodes clockwise. The result has `len()`
+    /// elements, and is empty if the hash ring is empty.
+    pub fn failover_order<U: Hash>(&self, key: &U) -> Vec<&T> {
+        self.iter(self.key(key))
+            .map(|node| &self.data[node.index].data)
+            .collect()
+    }
+
+    /// Returns the `NodeRef` for the node containing the first `prefix_len`
+    /// `/`-separated segments of `full_key`, or an error if the hash ring is
+    /// empty. Keys sharing that prefix are thus always co-located on the same
+    /// node. If `full_key` has fewer segments, the whole key is used.
+    pub fn get_by_prefix(
+        &self,
+        full_key: &str,
+        prefix_len: usize,
+    ) -> Result<NodeRef<'_, T, S>, Error> {
+        let prefix = match prefix_len.checked_sub(1) {
+            Some(n) => full_key
+                .match_indices('/')
+                .nth(n)
+                .map_or(full_key, |(end, _)| &full_key[..end]),
+            None => "",
+        };
+
+        self.get_by_hash(&prefix)
+    }
+
+    /// Similar to `get_by_hash()`, but if several nodes share the owner's
+    /// position on the ring, picks the one minimizing the hash of `(node,
+    /// key)`. The choice is reproducible across processes, and spreads keys
+    /// among the colliding nodes.
+    pub fn get_stable<U: Hash>(&self, key: &U) -> Result<NodeRef<'_, T, S>, Error> {
+        let hash = self.key(key);
+        let owner = self.get_by_key(&hash)?;
+
+        let index = self
+            .collision_run(owner.index)
+            .min_by_key(|&index| self.hash_builder.get_key((&self.data[index].data, key)))
+            .unwrap_or(owner.index);
+        self.observe(&hash, index, 0);
+
+        self.get_by_index(index)
+    }
+
+    /// Returns the owner index of each of `keys`, in order. All keys are hashed
+    /// before any of them are looked up, keeping both phases in tight loops.
+    /// Like `get_by_hash()`, the observer is notified of every lookup. Returns
+    /// an empty vector if the hash ring is empty.
+    pub fn owners_of<U: Hash>(&self, keys: &[U]) -> Vec<usize> {
+        if self.data.is_empty() {
+            return Vec::new();
         }
+
+        let keys = keys.iter().map(|key| self.key(key)).collect::<Vec<_>>();
+
+        keys.iter()
+            .filter_map(|key| {
+                let index = self.get_by_key(key).ok()?.index;
+                self.observe(key, index, 0);
+
+                Some(index)
+            })
+            .collect()
     }
-}
 
-impl<'a, T, S> Iterator for Iter<'a, T, S>
-where
-    T: Hash,
-    S: RingHasher,
-{
-    type Item = NodeRef<'a, T, S>;
+    /// Similar to `get_by_hash()`, but nodes sharing the owner's position on
+    /// the ring are chosen between using rendezvous hashing: the node with
+    /// the highest hash of `(key, node)` wins. Unlike `get_by_key()`, which
+    /// always resolves a collision to the same node, this ensures that no
+    /// colliding node is starved of keys.
+    pub fn get_fair<U: Hash>(&self, key: &U) -> Result<NodeRef<'_, T, S>, Error> {
+        let hash = self.key(key);
+        let owner = self.get_by_key(&hash)?;
+
+        let index = self
+            .collision_run(owner.index)
+            .max_by_key(|&index| self.hash_builder.get_key((key, &self.data[index].data)))
+            .unwrap_or(owner.index);
+        self.observe(&hash, index, 0);
+
+        self.get_by_index(index)
+    }
+
+    /// Picks one of the first `fanout` distinct nodes clockwise from `key`,
+    /// with probability proportional to `weight_of`. Like `get_n()`, the
+    /// extra points of weighted nodes are skipped. The choice is
+    /// deterministic for a given key, using a hash of `key` as the source
+    /// of randomness. Falls back to the owner if all candidates have a
+    /// weight of zero. Returns an error if the hash ring is empty.
+    pub fn get_weighted_choice<U, F>(
+        &self,
+        key: &U,
+        weight_of: F,
+        fanout: usize,
+    ) -> Result<NodeRef<'_, T, S>, Error>
+    where
+        T: PartialEq,
+        U: Hash,
+        F: Fn(&T) -> u32,
+    {
+        let candidates = self.distinct_nodes(key, fanout.max(1), |a, b| a == b);
+        let weighted = candidates
+            .into_iter()
+            .map(|node| (weight_of(node.data()) as u64, node))
+            .collect::<Vec<_>>();
+
+        let total = weighted.iter().map(|(weight, _)| weight).sum::<u64>();
+        let mut point = random_u64(key).checked_rem(total).unwrap_or(0);
+
+        let mut owner = None;
+        for (weight, node) in weighted {
+            if point < weight {
+                return Ok(node);
+            }
+
+            point -= weight;
+            owner.get_or_insert(node);
+        }
+
+        owner.ok_or(Error::NodeNotFound)
+    }
+
+    /// Returns the mean size of the range owned by each node, or `0` if the
+    /// hash ring is empty.
+    ///
+    /// Following the convention of `KeyRange::size()`, the whole keyspace has a
+    /// size of `S::Key::MAX`, which is also the total size of the ranges owned
+    /// by all nodes. The mean is thus `S::Key::MAX / len()`.
+    pub fn mean_partition_size(&self) -> S::Key
+    where
+        S::Key: RingKey,
+    {
+        S::Key::MAX
+            .checked_div_usize(self.len())
+            .unwrap_or(S::Key::MIN)
+    }
+
+    /// Returns the fraction of the keyspace owned by `node`, summing the ranges
+    /// of all of its points, or `0.0` if the hash ring doesn't contain it. As
+    /// in `load_distribution()`, nodes sharing their key with the node before
+    /// them own nothing. Sizes are converted to `f64`, so fractions of wide
+    /// keyspaces, such as the `u128` keys of `Blake3HashBuilder`, lose some
+    /// precision.
+    pub fn ownership_fraction(&self, node: &T) -> f64
+    where
+        T: PartialEq,
+        S::Key: RingKey,
+    {
+        let size = self
+            .iter(None)
+            .filter(|other| other.data() == node)
+            .filter(|other| self.len() == 1 || other.prev().key() != other.key())
+            .map(|other| other.owned_range().size().to_f64())
+            .sum::<f64>();
+
+        size / S::Key::MAX.to_f64()
+    }
+
+    /// Counts the nodes whose keys fall in each of `buckets` equal slices of
+    /// the keyspace, in order, which shows how evenly the nodes are spread.
+    /// Returns an empty vector if `buckets` is zero.
+    pub fn position_histogram(&self, buckets: usize) -> Vec<usize>
+    where
+        S::Key: RingKey,
+    {
+        let mut counts = vec![0; buckets];
+
+        if buckets == 0 {
+            return counts;
+        }
+
+        for node in &self.data {
+            let bucket = (node.key.to_f64() / S::Key::MAX.to_f64() * buckets as f64) as usize;
+            counts[bucket.min(buckets - 1)] += 1;
+        }
+
+        counts
+    }
+
+    /// Returns the ranges of keys owned by each host, merging the ranges of
+    /// adjacent nodes that belong to the same host (as determined by
+    /// `host_of`). Runs wrapping around the end of the ring are merged as well,
+    /// so a ring with a single host yields one range covering the whole ring.
+    pub fn coalesced_ranges<K, F>(&self, host_of: F) -> Vec<(K, KeyRange<S::Key>)>
+    where
+        S::Key: RingKey,
+        K: Eq,
+        F: Fn(&T) -> K,
+    {
+        let mut runs: Vec<(K, KeyRange<S::Key>)> = Vec::new();
+
+        for node in self.iter(None) {
+            let host = host_of(node.data());
+            let range = node.owned_range();
+
+            match runs.last_mut() {
+                Some((last, last_range)) if *last == host => last_range.end = range.end,
+                _ => runs.push((host, range)),
+            }
+        }
+
+        if runs.len() > 1 && runs.first().map(|run| &run.0) == runs.last().map(|run| &run.0) {
+            if let Some((_, range)) = runs.pop() {
+                runs[0].1.start = range.start;
+            }
+        }
+
+        runs
+    }
+
+    /// Routes each of `keys`, and returns the number of keys routed to each
+    /// node, indexed by node index. Returns an empty vector if the hash ring is
+    /// empty.
+    pub fn owned_counts<U: Hash>(&self, keys: impl IntoIterator<Item = U>) -> Vec<u64> {
+        let mut counts = vec![0; self.len()];
+
+        if !counts.is_empty() {
+            for key in keys {
+                if let Ok(index) = self.owner_index(&key) {
+                    counts[index] += 1;
+                }
+            }
+        }
+
+        counts
+    }
+
+    /// Routes a stream of length-prefixed keys read from `reader`, calling
+    /// `on_route` with the owner index and bytes of each key. Every key is
+    /// prefixed by its length as a big-endian `u32`, and is hashed as a `&[u8]`
+    /// (so it's routed the same as `get_by_hash(&bytes)`).
+    ///
+    /// Returns once the stream ends between two keys. Returns an error if
+    /// reading fails, the stream ends in the middle of a key, or the hash ring
+    /// is empty, and an `io::ErrorKind::InvalidData` error if a key is longer
+    /// than `MAX_STREAMED_KEY_LEN`.
+    #[cfg(feature = "std")]
+    pub fn route_reader<R, F>(&self, mut reader: R, mut on_route: F) -> io::Result<()>
+    where
+        R: Read,
+        F: FnMut(usize, &[u8]),
+    {
+        let mut len = [0; 4];
+        let mut key = Vec::new();
+
+        loop {
+            match reader.read(&mut len[..1]) {
+                Ok(0) => return Ok(()),
+                Ok(_) => reader.read_exact(&mut len[1..])?,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+
+            let len = u32::from_be_bytes(len) as usize;
+
+            if len > MAX_STREAMED_KEY_LEN {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("key length {len} exceeds {MAX_STREAMED_KEY_LEN} bytes"),
+                ));
+            }
+
+            key.resize(len, 0);
+            reader.read_exact(&mut key)?;
+
+            let index = self
+                .owner_index(&key.as_slice())
+                .map_err(io::Error::other)?;
+
+            on_route(index, &key);
+        }
+    }
+
+    /// Precomputes the owner index of each of `keys`. The returned map is a
+    /// snapshot, and is not updated when nodes are added to or removed from the
+    /// ring. Returns an empty map if the hash ring is empty.
+    #[cfg(feature = "std")]
+    pub fn compile_routes<U: Hash + Eq + Clone>(&self, keys: &[U]) -> HashMap<U, usize> {
+        keys.iter()
+            .filter_map(|key| Some((key.clone(), self.owner_index(key).ok()?)))
+            .collect()
+    }
+
+    /// Returns the `NodeRef` by node index within the hash ring, or an error if
+    /// the hash ring is empty.
+    #[inline]
+    pub fn get_by_index(&self, index: usize) -> Result<NodeRef<'_, T, S>, Error> {
+        if index < self.len() {
+            Ok(NodeRef { ring: self, index })
+        } else {
+            Err(Error::NodeNotFound)
+        }
+    }
+
+    /// Returns a mutable reference to the data of `node`, or an error if the
+    /// hash ring does not contain the `node`. Only fields that don't affect the
+    /// data's hash may be modified, since the node would otherwise no longer
+    /// be found at its position on the ring.
+    ///
+    /// Nodes added with `add_weighted_node()` keep a copy of their data for
+    /// each point, so they are rejected with `Error::NodeNotFound`; use
+    /// `update_node()` to change them instead.
+    pub fn data_mut(&mut self, node: &T) -> Result<&mut T, Error> {
+        let key = self.key(node);
+
+        match self.find_node(&key) {
+            Ok(index) if self.weighted_point(&key).is_none() => Ok(&mut self.data[index].data),
+            _ => Err(Error::NodeNotFound),
+        }
+    }
+
+    /// Returns a mutable reference to the data of the node containing `key`,
+    /// or an error if the hash ring is empty. As with `data_mut()`, only fields
+    /// that don't affect the data's hash may be modified.
+    pub fn get_by_hash_mut<U: Hash>(&mut self, key: &U) -> Result<&mut T, Error> {
+        let index = self.owner_index(key)?;
+
+        Ok(&mut self.data[index].data)
+    }
+
+    /// Returns true if the ring contains `node`, including nodes added with
+    /// `add_weighted_node()`.
+    #[inline]
+    pub fn contains_node(&self, node: &T) -> bool {
+        self.find_node(&self.key(node)).is_ok()
+            || self.weighted_point(&self.key(&(node, 0u32))).is_some()
+    }
+
+    /// Searches the ring for `node` and returns its `NodeRef`, or an error if
+    /// the node is not found.
+    #[inline]
+    pub fn node(&self, node: &T) -> Result<NodeRef<'_, T, S>, Error> {
+        if self.data.is_empty() {
+            return Err(Error::NodeNotFound);
+        }
+
+        let key = self.key(node);
+
+        let Ok(index) = self.find_node(&key) else {
+            return Err(Error::NodeNotFound);
+        };
+
+        self.get_by_index(index)
+    }
+
+    pub fn iter(&self, start: impl Into<Option<S::Key>>) -> Iter<'_, T, S> {
+        let start_node = if let Some(start_key) = start.into() {
+            self.get_by_key(&start_key)
+        } else {
+            self.get_by_index(0)
+        };
+
+        start_node.map(Iter::new).unwrap_or(Iter::empty())
+    }
+
+    /// Similar to `iter()`, but stops after at most `n` nodes. The iterator
+    /// never yields a node twice, even if `n` exceeds the number of nodes.
+    pub fn iter_take(&self, start: impl Into<Option<S::Key>>, n: usize) -> Iter<'_, T, S> {
+        let mut iter = self.iter(start);
+        iter.remaining = iter.remaining.min(n);
+        iter
+    }
+
+    /// Returns an iterator over all nodes in ascending key order, starting at
+    /// index 0. Unlike `iter()`, it doesn't wrap around the ring.
+    pub fn nodes(&self) -> impl ExactSizeIterator<Item = NodeRef<'_, T, S>> + '_ {
+        (0..self.data.len()).map(move |index| NodeRef { ring: self, index })
+    }
+
+    /// Returns an iterator over all nodes in ascending key order, paired with
+    /// their `range()`, i.e. from the node's key up to the next node's key. A
+    /// single node's range covers the whole ring.
+    pub fn ranges(&self) -> impl Iterator<Item = (NodeRef<'_, T, S>, KeyRange<S::Key>)> + '_ {
+        self.nodes().map(|node| {
+            let range = node.range();
+            (node, range)
+        })
+    }
+
+    /// Internal method passing a lookup of `key` to the observer, if there is
+    /// one.
+    #[inline]
+    fn observe(&self, key: &S::Key, index: usize, hops: usize) {
+        if let Some(observer) = &self.observer {
+            observer(LookupEvent {
+                key: key.clone(),
+                index,
+                hops,
+            });
+        }
+    }
+
+    /// Internal method for walking the ring clockwise from the node containing
+    /// `key`, collecting up to `n` nodes that are not the `same` as any of the
+    /// nodes collected before them.
+    fn distinct_nodes<U, F>(&self, key: &U, n: usize, same: F) -> Vec<NodeRef<'_, T, S>>
+    where
+        U: Hash,
+        F: Fn(&T, &T) -> bool,
+    {
+        let mut nodes: Vec<NodeRef<'_, T, S>> = Vec::new();
+
+        for node in self.iter(self.key(key)) {
+            if nodes.len() == n {
+                break;
+            }
+
+            if !nodes.iter().any(|other| same(other.data(), node.data())) {
+                nodes.push(node);
+            }
+        }
+
+        nodes
+    }
+
+    /// Internal method returning the indices of all nodes sharing the key of
+    /// the node at `index`.
+    fn collision_run(&self, index: usize) -> Range<usize> {
+        let key = &self.data[index].key;
+
+        let start = self.data[..index]
+            .iter()
+            .rposition(|node| &node.key != key)
+            .map_or(0, |i| i + 1);
+
+        let end = self.data[index..]
+            .iter()
+            .position(|node| &node.key != key)
+            .map_or(self.data.len(), |i| index + i);
+
+        start..end
+    }
+
+    /// Internal method for traversing the hash ring.
+    #[inline]
+    fn find_node(&self, key: &S::Key) -> Result<usize, usize> {
+        self.data.binary_search_by(|node| node.key.cmp(key))
+    }
+
+    /// Internal method returning the indices of all points of `node`: a single
+    /// one for nodes added with `add_node()`, or one per point for nodes added
+    /// with `add_weighted_node()`. Points are only looked up in the table of
+    /// weighted points, so other nodes sharing their keys are never included.
+    fn point_indices(&self, node: &T) -> Result<Vec<usize>, Error> {
+        let Some(weight) = self
+            .weighted_point(&self.key(&(node, 0u32)))
+            .map(|weighted| weighted.total)
+        else {
+            let key = self.key(node);
+
+            return match self.find_node(&key) {
+                Ok(index) if self.weighted_point(&key).is_none() => Ok(vec![index]),
+                _ => Err(Error::NodeNotFound),
+            };
+        };
+
+        Ok((0..weight)
+            .filter_map(|point| {
+                let key = self.key(&(node, point));
+                self.weighted_point(&key)?;
+                self.find_node(&key).ok()
+            })
+            .collect())
+    }
+
+    /// Internal method for searching the table of weighted points by key.
+    #[inline]
+    fn find_weighted_point(&self, key: &S::Key) -> Result<usize, usize> {
+        self.weights
+            .binary_search_by(|weighted| weighted.key.cmp(key))
+    }
+
+    /// Internal method returning the weighted point with `key`, if the node
+    /// with that key was added with `add_weighted_node()`.
+    #[inline]
+    fn weighted_point(&self, key: &S::Key) -> Option<&WeightedPoint<S::Key>> {
+        self.find_weighted_point(key)
+            .ok()
+            .map(|index| &self.weights[index])
+    }
+
+    /// Internal method dropping the weighted points whose nodes were removed.
+    fn prune_weights(&mut self) {
+        let data = &self.data;
+
+        self.weights.retain(|weighted| {
+            data.binary_search_by(|node| node.key.cmp(&weighted.key))
+                .is_ok()
+        });
+        self.weighted_nodes = self
+            .weights
+            .iter()
+            .filter(|weighted| weighted.point == 0)
+            .count();
+    }
+
+    /// Internal method for wrapping node index within the hash ring.
+    #[inline]
+    fn wrap_index(&self, index: usize) -> usize {
+        index % self.data.len()
+    }
+}
+
+impl<T, S> HashRing<T, S>
+where
+    T: Hash,
+    S: RingHasher<Key = u64>,
+{
+    /// Simulates removing the nodes in `order` one at a time, and returns the
+    /// fraction of the keyspace that changes owner at each step.
+    ///
+    /// Removing a node only moves the keys it owned, so each step's fraction is
+    /// the size of the removed node's range relative to the whole keyspace.
+    /// Nodes that are not in the ring (at that step) move nothing and are
+    /// reported as `0.0`. The ring itself is not modified.
+    pub fn simulate_removals(&self, order: &[T]) -> Vec<f64>
+    where
+        T: Clone,
+    {
+        let mut ring = self.clone();
+
+        order
+            .iter()
+            .map(|node| {
+                let Ok(node_ref) = ring.node(node) else {
+                    return 0.0;
+                };
+
+                let moved = node_ref.owned_range().size() as f64 / u64::MAX as f64;
+                let index = node_ref.index;
+
+                ring.data.remove(index);
+                ring.prune_weights();
+
+                moved
+            })
+            .collect()
+    }
+
+    /// Returns the `NodeRef` for the node containing `key`, along with the
+    /// fraction of the keyspace owned by that node, or an error if the hash
+    /// ring is empty. Callers can shed load when the fraction is abnormally
+    /// large, as a proxy for the node being overloaded. As in
+    /// `load_distribution()`, nodes sharing their key with the node before
+    /// them own nothing.
+    pub fn get_with_load_hint<U: Hash>(&self, key: &U) -> Result<(NodeRef<'_, T, S>, f64), Error> {
+        self.get_by_hash(key).map(|node| {
+            let share = if self.len() == 1 || node.prev().key() != node.key() {
+                node.owned_range().size() as f64 / u64::MAX as f64
+            } else {
+                0.0
+            };
+
+            (node, share)
+        })
+    }
+
+    /// Returns the fraction of the keyspace owned by each distinct node,
+    /// summing the ranges of all its points, normalized to sum to `1.0`. The
+    /// fractions of a well-balanced ring are all close to `1 / n` for `n`
+    /// nodes. Nodes sharing their key with the node before them own nothing.
+    #[cfg(feature = "std")]
+    pub fn load_distribution(&self) -> HashMap<&T, f64>
+    where
+        T: Eq,
+    {
+        let mut sizes = HashMap::new();
+
+        for node in self.iter(None) {
+            let size = if self.len() == 1 || node.prev().key() != node.key() {
+                node.owned_range().size() as u128
+            } else {
+                0
+            };
+
+            *sizes.entry(&self.data[node.index].data).or_insert(0u128) += size;
+        }
+
+        let total = sizes.values().sum::<u128>().max(1) as f64;
+
+        sizes
+            .into_iter()
+            .map(|(node, size)| (node, size as f64 / total))
+            .collect()
+    }
+
+    /// Picks a random node, with each node's probability proportional to the
+    /// size of the range it owns, or returns `None` if the hash ring is empty.
+    /// Nodes sharing their key with the node before them own nothing, and are
+    /// never picked. If all nodes share a single key, the first one owns every
+    /// key and is always picked.
+    #[cfg(feature = "rand")]
+    pub fn sample_by_ownership<R: rand::Rng>(&self, rng: &mut R) -> Option<NodeRef<'_, T, S>> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let cumulative = self
+            .iter(None)
+            .scan(0u128, |total, node| {
+                if self.len() == 1 || node.prev().key() != node.key() {
+                    *total += node.owned_range().size() as u128;
+                }
+
+                Some(*total)
+            })
+            .collect::<Vec<_>>();
+
+        let total = cumulative[cumulative.len() - 1];
+
+        if total == 0 {
+            return self.get_by_index(0).ok();
+        }
+
+        let point = rng.gen_range(0..total);
+        let index = cumulative.partition_point(|&total| total <= point);
+
+        self.get_by_index(index).ok()
+    }
+
+    /// Returns the `NodeRef` for the node containing the key at fraction `p` of
+    /// the keyspace, i.e. `p * 2^64`, or an error if the hash ring is empty.
+    /// Values of `p` outside of `[0, 1)` are clamped to the keyspace.
+    pub fn node_at_fraction(&self, p: f64) -> Result<NodeRef<'_, T, S>, Error> {
+        // Float to integer casts saturate, which clamps the key. `u64::MAX` is
+        // rounded to `2^64` when converted.
+        let key = (p * u64::MAX as f64) as u64;
+
+        self.get_by_key(&key)
+    }
+
+    /// Returns the `NodeRef` for the node containing `key`, along with the
+    /// distance from the key to the end of the owner's range, or an error if
+    /// the hash ring is empty. Keys with little headroom are close to a
+    /// partition boundary, and more likely to move on topology changes.
+    pub fn locate_with_headroom<U: Hash>(
+        &self,
+        key: &U,
+    ) -> Result<(NodeRef<'_, T, S>, u64), Error> {
+        let key = self.key(key);
+
+        self.get_by_key(&key).map(|node| {
+            let headroom = node.key().wrapping_sub(key);
+            (node, headroom)
+        })
+    }
+
+    /// Returns the `NodeRef` for the node containing `key`, or an error if the
+    /// hash ring is empty. Same as `get_by_key()`, but uses interpolation
+    /// search, which is faster on large rings since node keys are uniformly
+    /// distributed. Falls back to binary search if the keys are not.
+    pub fn get_by_key_interpolated(&self, key: &u64) -> Result<NodeRef<'_, T, S>, Error> {
+        if self.data.is_empty() {
+            return Err(Error::NodeNotFound);
+        }
+
+        let index = self.interpolation_search(*key);
+
+        self.get_by_index(self.wrap_index(index))
+    }
+
+    /// Shifts the position of every node on the ring by `delta` (wrapping), and
+    /// re-sorts the ring. A key `k` is then owned by the node that owned
+    /// `k - delta` before the rotation.
+    ///
+    /// Note that rotated nodes are no longer at the keys their data hashes to,
+    /// so the ring can only be used for key-based lookups afterwards. Methods
+    /// locating nodes by their data, such as `node()`, `contains_node()`,
+    /// `remove_node()`, `update_node()`, `data_mut()` and `weight_of()`, no
+    /// longer find them, `add_node()` no longer detects duplicates, and
+    /// `verify_keys()` reports every rotated node. Rotating by `-delta`
+    /// (wrapping) restores the ring.
+    pub fn rotate_keys(&mut self, delta: u64) {
+        for node in &mut self.data {
+            node.key = node.key.wrapping_add(delta);
+        }
+
+        for weighted in &mut self.weights {
+            weighted.key = weighted.key.wrapping_add(delta);
+        }
+
+        self.weights.sort_by_key(|weighted| weighted.key);
+
+        self.sort();
+    }
+
+    /// Renders the ring as a Graphviz DOT digraph. Each node points to its
+    /// clockwise successor, and the edge is labeled with the size of the range
+    /// between them.
+    pub fn to_dot(&self) -> String
+    where
+        T: Debug,
+    {
+        let mut dot = String::from("digraph ring {\n");
+
+        for node in self.iter(None) {
+            let label = format!("{:?}", node.data())
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"");
+
+            // Writing to a `String` can't fail.
+            let _ = writeln!(dot, "    n{} [label=\"{}\"];", node.index, label);
+        }
+
+        for node in self.iter(None) {
+            let _ = writeln!(
+                dot,
+                "    n{} -> n{} [label=\"{}\"];",
+                node.index,
+                node.next().index,
+                node.range().size()
+            );
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Internal method returning the index of the first node whose key is not
+    /// less than `key`, using interpolation search. After a fixed number of
+    /// probes, the remaining range is binary searched instead, which bounds
+    /// the cost on non-uniform keys.
+    fn interpolation_search(&self, key: u64) -> usize {
+        const MAX_PROBES: usize = 8;
+
+        // All nodes before `lo` are less than `key`, and all nodes from `hi`
+        // onwards are not.
+        let (mut lo, mut hi) = (0, self.data.len());
+
+        for _ in 0..MAX_PROBES {
+            if lo == hi || key <= self.data[lo].key {
+                return lo;
+            }
+
+            let (first, last) = (self.data[lo].key, self.data[hi - 1].key);
+            if key > last {
+                return hi;
+            }
+
+            // `first < key <= last`, so the probe is within `lo..hi`.
+            let offset = (key - first) as u128 * (hi - 1 - lo) as u128 / (last - first) as u128;
+            let probe = lo + offset as usize;
+
+            if self.data[probe].key < key {
+                lo = probe + 1;
+            } else {
+                hi = probe;
+            }
+        }
+
+        lo + self.data[lo..hi].partition_point(|node| node.key < key)
+    }
+}
+
+/// Convenience methods for rings of byte string nodes. Byte vectors and slices
+/// hash identically, so nodes and keys can be passed as `&[u8]` without
+/// allocating for lookups.
+impl<S: RingHasher> HashRing<Vec<u8>, S> {
+    /// Adds a node with a copy of `bytes` as its data. Returns the new node's
+    /// index, or an error if the hash ring already contains the node.
+    pub fn add_bytes(&mut self, bytes: &[u8]) -> Result<usize, Error> {
+        self.add_node(bytes.to_vec())
+    }
+
+    /// Returns the `NodeRef` for the node containing `bytes`, or an error if
+    /// the hash ring is empty.
+    #[inline]
+    pub fn get_by_bytes(&self, bytes: &[u8]) -> Result<NodeRef<'_, Vec<u8>, S>, Error> {
+        self.get_by_hash(&bytes)
+    }
+}
+
+/// The owner of a key along with the range of keys it owns. See
+/// `HashRing::ownership_proof()`.
+pub type OwnershipProof<'a, T, S> = (NodeRef<'a, T, S>, KeyRange<<S as RingHasher>::Key>);
+
+/// Reference to a hash ring node. Acts as an iterator (using `prev()` and
+/// `next()` methods), and provides additional node data like range and hash
+/// key.
+#[derive(Clone)]
+pub struct NodeRef<'a, T, S: RingHasher> {
+    ring: &'a HashRing<T, S>,
+    index: usize,
+}
+
+impl<'a, T, S: RingHasher> Debug for NodeRef<'a, T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NodeRef")
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, T, S> NodeRef<'a, T, S>
+where
+    T: Hash,
+    S: RingHasher,
+{
+    /// Returns the node's hash key.
+    #[inline]
+    pub fn key(&self) -> &S::Key {
+        &self.node().key
+    }
+
+    /// Returns an identifier for the node, e.g. for tagging traces. Unlike the
+    /// node's index, which changes as other nodes are added and removed, the
+    /// identifier is the node's key and stays the same for as long as the node
+    /// is in the ring.
+    #[inline]
+    pub fn stable_id(&self) -> S::Key {
+        self.key().clone()
+    }
+
+    /// Returns the node's data.
+    #[inline]
+    pub fn data(&self) -> &T {
+        &self.node().data
+    }
+
+    /// Returns the previous node on the hash ring. If the hash ring contains
+    /// only one node, the returned reference will be for the same node.
+    #[inline]
+    pub fn prev(&self) -> Self {
+        let ring = self.ring;
+
+        Self {
+            ring,
+            index: ring.wrap_index(ring.len() + self.index - 1),
+        }
+    }
+
+    /// Returns the next node on the hash ring. If the hash ring contains
+    /// only one node, the returned reference will be for the same node.
+    #[inline]
+    pub fn next(&self) -> Self {
+        let ring = self.ring;
+
+        Self {
+            ring,
+            index: ring.wrap_index(self.index + 1),
+        }
+    }
+
+    /// Returns the node `offset` positions away on the hash ring, moving
+    /// clockwise for positive offsets and counter-clockwise for negative ones,
+    /// in constant time. `step(1)` is equivalent to `next()`, and `step(-1)` to
+    /// `prev()`.
+    #[inline]
+    pub fn step(&self, offset: isize) -> Self {
+        let ring = self.ring;
+
+        if ring.is_empty() {
+            return Self {
+                ring,
+                index: self.index,
+            };
+        }
+
+        let offset = offset.rem_euclid(ring.len() as isize) as usize;
+
+        Self {
+            ring,
+            index: ring.wrap_index(self.index + offset),
+        }
+    }
+
+    /// Returns the nodes range on the hash ring.
+    #[inline]
+    pub fn range(&self) -> KeyRange<S::Key> {
+        KeyRange {
+            start: self.key().clone(),
+            end: self.next().key().clone(),
+        }
+    }
+
+    #[inline]
+    fn node(&self) -> &Node<S::Key, T> {
+        // Safe unwrap, since the node ref would not exist otherwise.
+        self.ring.data.get(self.index).unwrap()
+    }
+}
+
+impl<'a, T, S> NodeRef<'a, T, S>
+where
+    T: Hash,
+    S: RingHasher<Key = u64>,
+{
+    /// Returns a short, human-readable tag for the node, e.g. for logging. The
+    /// tag is the top 40 bits of the node's key encoded as 8 base32
+    /// characters, so it stays the same for as long as the node is in the
+    /// ring.
+    pub fn short_tag(&self) -> String {
+        const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+        (0..8)
+            .map(|i| ALPHABET[(self.key() >> (59 - i * 5)) as usize & 0x1f] as char)
+            .collect()
+    }
+}
+
+impl<'a, T, S> NodeRef<'a, T, S>
+where
+    T: Hash,
+    S: RingHasher,
+    S::Key: RingKey,
+{
+    /// Returns the clockwise distance in the keyspace from this node's key to
+    /// `other`'s, using wrapping arithmetic. The distance from a node to
+    /// itself is 0.
+    #[inline]
+    pub fn distance_to(&self, other: &NodeRef<'_, T, S>) -> S::Key {
+        other.key().wrapping_sub(*self.key())
+    }
+
+    /// Internal method returning the range of keys routed to this node, i.e.
+    /// the keys after the previous node's key, up to and including this node's
+    /// key. Since ranges exclude their end, both bounds are shifted by one.
+    #[inline]
+    fn owned_range(&self) -> KeyRange<S::Key> {
+        KeyRange {
+            start: self.prev().key().wrapping_next(),
+            end: self.key().wrapping_next(),
+        }
+    }
+}
+
+/// Iterator over the preference list of a key. See `HashRing::replicas_iter()`.
+pub type ReplicaIter<'a, T, S> = Iter<'a, T, S>;
+
+/// Iterator walking the ring once, clockwise from a starting node. Walking it
+/// from the back goes counter-clockwise, starting at the node before the
+/// starting node.
+pub struct Iter<'a, T, S: RingHasher> {
+    front: Option<NodeRef<'a, T, S>>,
+    remaining: usize,
+}
+
+impl<'a, T, S> Iter<'a, T, S>
+where
+    T: Hash,
+    S: RingHasher,
+{
+    pub fn new(node: NodeRef<'a, T, S>) -> Self {
+        Self {
+            remaining: node.ring.len(),
+            front: Some(node),
+        }
+    }
+
+    pub fn empty() -> Self {
+        Self {
+            front: None,
+            remaining: 0,
+        }
+    }
+}
+
+impl<'a, T, S> Iterator for Iter<'a, T, S>
+where
+    T: Hash,
+    S: RingHasher,
+{
+    type Item = NodeRef<'a, T, S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let current = self.front.take()?;
+        self.remaining -= 1;
+        self.front = Some(current.next());
+
+        Some(current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T, S> DoubleEndedIterator for Iter<'a, T, S>
+where
+    T: Hash,
+    S: RingHasher,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let front = self.front.as_ref()?;
+        self.remaining -= 1;
+
+        let ring = front.ring;
+
+        Some(NodeRef {
+            ring,
+            index: ring.wrap_index(front.index + self.remaining),
+        })
+    }
+}
+
+/// Consuming iterator over the nodes of a ring, in ascending key order. Nodes
+/// added with `HashRing::add_weighted_node()` are yielded once.
+pub struct IntoIter<T, K> {
+    inner: alloc::vec::IntoIter<Node<K, T>>,
+    weights: Vec<WeightedPoint<K>>,
+}
+
+impl<T, K: Ord> Iterator for IntoIter<T, K> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let weights = &self.weights;
+
+        self.inner
+            .find(|node| {
+                !weights
+                    .binary_search_by(|weighted| weighted.key.cmp(&node.key))
+                    .is_ok_and(|index| weights[index].point != 0)
+            })
+            .map(|node| node.data)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.inner.size_hint().1)
+    }
+}
+
+impl<T, S: RingHasher> IntoIterator for HashRing<T, S> {
+    type IntoIter = IntoIter<T, S::Key>;
+    type Item = T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            inner: self.data.into_iter(),
+            weights: self.weights,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{
+            collections::{HashMap, HashSet},
+            net::{IpAddr, SocketAddr},
+            str::FromStr,
+            sync::Mutex,
+        },
+    };
+
+    #[derive(Debug, Copy, Clone, Hash, PartialEq)]
+    struct VNode {
+        id: usize,
+        addr: SocketAddr,
+    }
+
+    impl VNode {
+        fn new(ip: &str, port: u16, id: usize) -> Self {
+            let addr = SocketAddr::new(IpAddr::from_str(&ip).unwrap(), port);
+            VNode { id, addr }
+        }
+    }
+
+    fn ring_with_keys<T: Hash>(nodes: Vec<(u64, T)>) -> HashRing<T> {
+        let mut ring = HashRing::new();
+
+        for (key, data) in nodes {
+            ring.data.push(Node::new(key, data));
+        }
+
+        ring.sort();
+        ring
+    }
+
+    #[test]
+    fn add_and_remove_nodes() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+
+        assert_eq!(ring.len(), 0);
+        assert!(ring.is_empty());
+
+        let vnode1 = VNode::new("127.0.0.1", 1024, 1);
+        let vnode2 = VNode::new("127.0.0.1", 1024, 2);
+        let vnode3 = VNode::new("127.0.0.2", 1024, 1);
+
+        ring.add_node(vnode1).unwrap();
+        ring.add_node(vnode2).unwrap();
+        ring.add_node(vnode3).unwrap();
+        assert_eq!(ring.len(), 3);
+        assert!(!ring.is_empty());
+
+        ring.remove_node(&vnode2).unwrap();
+        assert_eq!(ring.len(), 2);
+
+        let vnode4 = VNode::new("127.0.0.2", 1024, 2);
+        let vnode5 = VNode::new("127.0.0.2", 1024, 3);
+        let vnode6 = VNode::new("127.0.0.3", 1024, 1);
+
+        ring.add_node(vnode4).unwrap();
+        ring.add_node(vnode5).unwrap();
+        ring.add_node(vnode6).unwrap();
+
+        ring.remove_node(&vnode1).unwrap();
+        ring.remove_node(&vnode3).unwrap();
+        ring.remove_node(&vnode6).unwrap();
+        assert_eq!(ring.len(), 2);
+    }
+
+    #[test]
+    fn get_nodes() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+
+        assert!(matches!(
+            ring.get_by_hash(&"foo"),
+            Err(super::Error::NodeNotFound)
+        ));
+
+        let vnode1 = VNode::new("127.0.0.1", 1024, 1);
+        let vnode2 = VNode::new("127.0.0.1", 1024, 2);
+        let vnode3 = VNode::new("127.0.0.2", 1024, 1);
+        let vnode4 = VNode::new("127.0.0.2", 1024, 2);
+        let vnode5 = VNode::new("127.0.0.2", 1024, 3);
+        let vnode6 = VNode::new("127.0.0.3", 1024, 1);
+
+        ring.add_node(vnode1).unwrap();
+        ring.add_node(vnode2).unwrap();
+        ring.add_node(vnode3).unwrap();
+        ring.add_node(vnode4).unwrap();
+        ring.add_node(vnode5).unwrap();
+        ring.add_node(vnode6).unwrap();
+
+        assert_eq!(ring.get_by_hash(&"foo").unwrap().data(), &vnode5);
+        assert_eq!(ring.get_by_hash(&"bar").unwrap().data(), &vnode3);
+        assert_eq!(ring.get_by_hash(&"baz").unwrap().data(), &vnode5);
+
+        assert_eq!(ring.get_by_hash(&"abc").unwrap().data(), &vnode2);
+        assert_eq!(ring.get_by_hash(&"def").unwrap().data(), &vnode2);
+        assert_eq!(ring.get_by_hash(&"ghi").unwrap().data(), &vnode6);
+
+        assert_eq!(ring.get_by_hash(&"cat").unwrap().data(), &vnode1);
+        assert_eq!(ring.get_by_hash(&"dog").unwrap().data(), &vnode5);
+        assert_eq!(ring.get_by_hash(&"bird").unwrap().data(), &vnode5);
+
+        // at least each node as a key
+        let mut nodes = vec![0; 6];
+        for x in 0..50_000 {
+            let node = ring.get_by_hash(&x).unwrap();
+            let node = node.data();
+            if vnode1 == *node {
+                nodes[0] += 1;
+            }
+            if vnode2 == *node {
+                nodes[1] += 1;
+            }
+            if vnode3 == *node {
+                nodes[2] += 1;
+            }
+            if vnode4 == *node {
+                nodes[3] += 1;
+            }
+            if vnode5 == *node {
+                nodes[4] += 1;
+            }
+            if vnode6 == *node {
+                nodes[5] += 1;
+            }
+        }
+        println!("{:?}", nodes);
+        assert!(nodes.iter().all(|x| *x != 0));
+    }
+
+    #[test]
+    fn advanced() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+
+        let vnode1 = VNode::new("127.0.0.1", 1024, 1);
+        let vnode2 = VNode::new("127.0.0.1", 1024, 2);
+
+        // Ok: no nodes were affected.
+        let result = ring.add_node(vnode1).unwrap();
+        assert_eq!(result, 0);
+        let vnode1_prev_range = ring.get_by_hash(&vnode1).unwrap().range();
+
+        // Error: duplicates are not allowed.
+        let result = ring.add_node(vnode1);
+        assert_eq!(result.unwrap_err(), super::Error::DuplicateNode);
+
+        let result = ring.add_node(vnode2).unwrap();
+        assert_eq!(result, 1);
+        let vnode1_curr_range = ring.get_by_hash(&vnode1).unwrap().range();
+
+        let vnode2_curr_range = ring.get_by_hash(&vnode2).unwrap().range();
+
+        assert_eq!(vnode1_prev_range.start, vnode1_curr_range.start);
+        assert_eq!(vnode1_prev_range.end, vnode2_curr_range.end);
+        assert_eq!(vnode1_curr_range.end, vnode2_curr_range.start);
+    }
+
+    #[test]
+    fn node_ref() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+
+        let vnode1 = VNode::new("127.0.0.1", 1024, 1);
+        let vnode2 = VNode::new("127.0.0.1", 1024, 2);
+
+        assert!(matches!(
+            ring.get_by_hash(&vnode1),
+            Err(super::Error::NodeNotFound)
+        ));
+
+        ring.add_node(vnode1).unwrap();
+
+        let node_ref = ring.get_by_hash(&vnode1).unwrap();
+        assert_eq!(node_ref.data(), &vnode1);
+        assert_eq!(node_ref.index, 0);
+        assert_eq!(node_ref.prev().index, 0);
+        assert_eq!(node_ref.next().index, 0);
+
+        ring.add_node(vnode2).unwrap();
+
+        let node_ref = ring.get_by_hash(&vnode2).unwrap();
+        assert_eq!(node_ref.data(), &vnode2);
+        assert_eq!(node_ref.prev().data(), &vnode1);
+        assert_eq!(node_ref.next().data(), &vnode1);
+    }
+
+    #[test]
+    fn node_range() {
+        // One node.
+        {
+            let mut ring: HashRing<VNode> = HashRing::new();
+            let node = VNode::new("127.0.0.1", 1024, 1);
+            let node_key = ring.key(&node);
+
+            ring.add_node(node).unwrap();
+
+            let range = ring.get_by_hash(&node).unwrap().range();
+            assert_eq!(range, KeyRange {
+                start: node_key,
+                end: node_key
+            });
+        }
+
+        // Multiple nodes.
+        {
+            let mut ring: HashRing<VNode> = HashRing::new();
+            let node1 = VNode::new("127.0.0.1", 1024, 1);
+            let node2 = VNode::new("127.0.0.1", 1024, 2);
+            let node_key1 = ring.key(&node1);
+            let node_key2 = ring.key(&node2);
+
+            ring.add_node(node1).unwrap();
+            ring.add_node(node2).unwrap();
+
+            let range = ring.get_by_hash(&node1).unwrap().range();
+            assert_eq!(range, KeyRange {
+                start: node_key1,
+                end: node_key2
+            });
+
+            let range = ring.get_by_hash(&node2).unwrap().range();
+            assert_eq!(range, KeyRange {
+                start: node_key2,
+                end: node_key1
+            });
+        }
+
+        // Invalid node.
+        {
+            let mut ring: HashRing<VNode> = HashRing::new();
+            let node1 = VNode::new("127.0.0.1", 1024, 1);
+            let node2 = VNode::new("127.0.0.1", 1024, 2);
+
+            ring.add_node(node1).unwrap();
+
+            let result = ring.node(&node2);
+            assert!(matches!(result, Err(super::Error::NodeNotFound)));
+        }
+    }
+
+    #[test]
+    fn iter() {
+        let node1 = VNode::new("127.0.0.1", 1024, 1);
+        let node2 = VNode::new("127.0.0.1", 1024, 2);
+        let node3 = VNode::new("127.0.0.1", 1024, 3);
+
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert!(ring.iter(None).next().is_none());
+
+        ring.add_node(node1.clone()).unwrap();
+        assert_eq!(ring.iter(None).next().unwrap().data(), &node1);
+
+        ring.add_node(node2.clone()).unwrap();
+        ring.add_node(node3.clone()).unwrap();
+
+        let mut iter = ring.iter(Some(ring.key(&node2)));
+
+        assert_eq!(iter.next().unwrap().data(), &node2);
+        assert_eq!(iter.next().unwrap().data(), &node3);
+        assert_eq!(iter.next().unwrap().data(), &node1);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn add_node_unchecked() {
+        let node1 = VNode::new("127.0.0.1", 1024, 3);
+        let node2 = VNode::new("127.0.0.1", 1024, 2);
+        let node3 = VNode::new("127.0.0.1", 1024, 1);
+
+        let mut ring1 = HashRing::new();
+        ring1.add_node(node1.clone()).unwrap();
+        ring1.add_node(node2.clone()).unwrap();
+        ring1.add_node(node3.clone()).unwrap();
+
+        let mut ring2 = HashRing::new();
+        ring2.add_node_unchecked(node1.clone());
+        ring2.add_node_unchecked(node2.clone());
+        ring2.add_node_unchecked(node3.clone());
+        ring2.sort();
+
+        assert_eq!(ring1.data, ring2.data);
+        assert!(ring1 == ring2);
+    }
+
+    #[test]
+    fn ring_eq() {
+        let ring1 = ring_with_keys(vec![(10, "a"), (20, "b")]);
+        let mut ring2 = ring_with_keys(vec![(20, "b"), (10, "a")]);
+        assert!(ring1 == ring2);
+
+        // Versions are not compared.
+        ring2.sort();
+        assert!(ring1 == ring2);
+
+        ring2.data[1].data = "c";
+        assert!(ring1 != ring2);
+
+        ring2.data[1] = Node::new(21, "b");
+        assert!(ring1 != ring2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn compile_routes() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert!(ring.compile_routes(&["tenant-1"]).is_empty());
+
+        ring.add_node(VNode::new("127.0.0.1", 1024, 1)).unwrap();
+        ring.add_node(VNode::new("127.0.0.1", 1024, 2)).unwrap();
+        ring.add_node(VNode::new("127.0.0.2", 1024, 1)).unwrap();
+
+        let tenants = (0..32).map(|id| format!("tenant-{id}")).collect::<Vec<_>>();
+        let routes = ring.compile_routes(&tenants);
+
+        assert_eq!(routes.len(), tenants.len());
+        for tenant in &tenants {
+            assert_eq!(routes[tenant], ring.owner_index(tenant).unwrap());
+        }
+    }
+
+    #[test]
+    fn rotate_keys() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        ring.add_node(VNode::new("127.0.0.1", 1024, 1)).unwrap();
+        ring.add_node(VNode::new("127.0.0.1", 1024, 2)).unwrap();
+        ring.add_node(VNode::new("127.0.0.2", 1024, 1)).unwrap();
+        ring.add_node(VNode::new("127.0.0.2", 1024, 2)).unwrap();
+
+        let delta = u64::MAX / 3;
+        let mut rotated = ring.clone();
+        rotated.rotate_keys(delta);
+
+        assert!(rotated
+            .data
+            .windows(2)
+            .all(|pair| pair[0].key < pair[1].key));
+        assert_eq!(rotated.verify_keys(), Err(vec![0, 1, 2, 3]));
+
+        for x in 0..1000 {
+            let key = ring.key(&x);
+            assert_eq!(
+                rotated.get_by_key(&key).unwrap().data(),
+                ring.get_by_key(&key.wrapping_sub(delta)).unwrap().data()
+            );
+        }
+
+        rotated.rotate_keys(delta.wrapping_neg());
+        assert_eq!(rotated.verify_keys(), Ok(()));
+        assert!(rotated == ring);
+    }
+
+    #[test]
+    fn get_cow() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert_eq!(ring.get_cow(&"foo"), Err(Error::NodeNotFound));
+
+        ring.add_node(VNode::new("127.0.0.1", 1024, 1)).unwrap();
+        ring.add_node(VNode::new("127.0.0.1", 1024, 2)).unwrap();
+
+        for key in ["foo", "bar", "baz"] {
+            let data = ring.get_cow(&key).unwrap();
+            assert!(matches!(data, Cow::Borrowed(_)));
+            assert_eq!(&*data, ring.get_by_hash(&key).unwrap().data());
+        }
+    }
+
+    #[test]
+    fn get_stable() {
+        let node1 = VNode::new("127.0.0.1", 1024, 1);
+        let node2 = VNode::new("127.0.0.1", 1024, 2);
+        let node3 = VNode::new("127.0.0.1", 1024, 3);
+
+        let mut ring: HashRing<VNode> = HashRing::new();
+        ring.add_node_unchecked(node1);
+        ring.add_node_unchecked(node2);
+        ring.add_node_unchecked(node3);
+
+        // Force a collision between the first two nodes.
+        ring.data[1].key = ring.data[0].key;
+        ring.sort();
+
+        let mut counts = [0; 3];
+        for x in 0..10_000 {
+            let node = ring.get_stable(&x).unwrap();
+            assert_eq!(ring.get_stable(&x).unwrap().index, node.index);
+
+            let id = node.data().id;
+            counts[id - 1] += 1;
+        }
+
+        assert!(counts.iter().all(|count| *count > 0));
+    }
+
+    #[test]
+    fn to_dot() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert_eq!(ring.to_dot(), "digraph ring {\n}\n");
+
+        ring.add_node(VNode::new("127.0.0.1", 1024, 1)).unwrap();
+        ring.add_node(VNode::new("127.0.0.1", 1024, 2)).unwrap();
+        ring.add_node(VNode::new("127.0.0.2", 1024, 1)).unwrap();
+
+        let dot = ring.to_dot();
+        assert!(dot.starts_with("digraph"));
+        assert_eq!(dot.matches(" -> ").count(), 3);
+        assert!(dot.contains("n2 -> n0"));
+    }
+
+    #[test]
+    fn get_by_prefix() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        ring.add_node(VNode::new("127.0.0.1", 1024, 1)).unwrap();
+        ring.add_node(VNode::new("127.0.0.1", 1024, 2)).unwrap();
+        ring.add_node(VNode::new("127.0.0.2", 1024, 1)).unwrap();
+
+        let expected = ring.get_by_hash(&"tenant/users").unwrap().index;
+        for id in 0..100 {
+            let key = format!("tenant/users/{id}");
+            assert_eq!(ring.get_by_prefix(&key, 2).unwrap().index, expected);
+        }
+
+        // Keys with fewer segments than the prefix are used whole.
+        assert_eq!(
+            ring.get_by_prefix("tenant/users", 5).unwrap().index,
+            expected
+        );
+
+        let owners = (0..100)
+            .map(|id| {
+                let key = format!("tenant-{id}/users/1");
+                ring.get_by_prefix(&key, 2).unwrap().index
+            })
+            .collect::<HashSet<_>>();
+        assert!(owners.len() > 1);
+    }
+
+    #[test]
+    fn owners_of() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert!(ring.owners_of(&[1, 2, 3]).is_empty());
+
+        ring.add_node(VNode::new("127.0.0.1", 1024, 1)).unwrap();
+        ring.add_node(VNode::new("127.0.0.1", 1024, 2)).unwrap();
+        ring.add_node(VNode::new("127.0.0.2", 1024, 1)).unwrap();
+
+        let keys = (0..1000).collect::<Vec<_>>();
+        let owners = ring.owners_of(&keys);
+
+        assert_eq!(owners.len(), keys.len());
+        for (key, owner) in keys.iter().zip(owners) {
+            assert_eq!(owner, ring.owner_index(key).unwrap());
+        }
+    }
+
+    #[test]
+    fn coalesced_ranges() {
+        let host_of = |node: &(&'static str, u32)| node.0;
+
+        // Interleaved hosts.
+        let ring = ring_with_keys(vec![
+            (10, ("a", 0)),
+            (20, ("a", 1)),
+            (30, ("b", 0)),
+            (40, ("a", 2)),
+            (50, ("b", 1)),
+        ]);
+        assert_eq!(ring.coalesced_ranges(host_of), vec![
+            ("a", KeyRange::new(51, 21)),
+            ("b", KeyRange::new(21, 31)),
+            ("a", KeyRange::new(31, 41)),
+            ("b", KeyRange::new(41, 51)),
+        ]);
+
+        // Clustered hosts, with a run wrapping around the end of the ring.
+        let ring = ring_with_keys(vec![
+            (10, ("a", 0)),
+            (20, ("b", 0)),
+            (30, ("b", 1)),
+            (40, ("a", 1)),
+        ]);
+        assert_eq!(ring.coalesced_ranges(host_of), vec![
+            ("a", KeyRange::new(31, 11)),
+            ("b", KeyRange::new(11, 31)),
+        ]);
+
+        // Single host.
+        let ring = ring_with_keys(vec![(10, ("a", 0)), (20, ("a", 1))]);
+        assert_eq!(ring.coalesced_ranges(host_of), vec![(
+            "a",
+            KeyRange::new(21, 21)
+        )]);
+    }
+
+    #[test]
+    fn fingerprint() {
+        let nodes = (1..=5)
+            .map(|id| VNode::new("127.0.0.1", 1024, id))
+            .collect::<Vec<_>>();
+
+        let mut ring1: HashRing<VNode> = HashRing::new();
+        for node in &nodes {
+            ring1.add_node(*node).unwrap();
+        }
+
+        let mut ring2: HashRing<VNode> = HashRing::new();
+        for node in nodes.iter().rev() {
+            ring2.add_node(*node).unwrap();
+        }
+
+        assert_eq!(ring1.fingerprint().len(), nodes.len());
+        assert_eq!(ring1.fingerprint(), ring2.fingerprint());
+        assert!(ring1.fingerprint().windows(2).all(|pair| pair[0] < pair[1]));
+
+        ring2.remove_node(&nodes[0]).unwrap();
+        assert_ne!(ring1.fingerprint(), ring2.fingerprint());
+    }
+
+    #[test]
+    fn get_fair() {
+        let ring = ring_with_keys(vec![
+            (u64::MAX / 2, "a"),
+            (u64::MAX / 2, "b"),
+            (u64::MAX / 4 * 3, "c"),
+        ]);
+
+        let mut counts = HashMap::new();
+        for x in 0..10_000 {
+            let node = ring.get_fair(&x).unwrap();
+            assert_eq!(ring.get_fair(&x).unwrap().data(), node.data());
+
+            *counts.entry(*node.data()).or_insert(0) += 1;
+        }
+
+        assert!(counts["a"] > 0);
+        assert!(counts["b"] > 0);
+        assert!(counts["c"] > 0);
+    }
+
+    #[test]
+    fn locate() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert_eq!(ring.locate(&"foo"), Err(Error::NodeNotFound));
+
+        ring.add_node(VNode::new("127.0.0.1", 1024, 1)).unwrap();
+        ring.add_node(VNode::new("127.0.0.1", 1024, 2)).unwrap();
+        ring.add_node(VNode::new("127.0.0.2", 1024, 1)).unwrap();
+
+        for x in 0..1000 {
+            let (index, range) = ring.locate(&x).unwrap();
+            assert_eq!(index, ring.owner_index(&x).unwrap());
+            assert!(range.contains(&ring.key(&x)));
+        }
+    }
+
+    #[test]
+    fn ownership_proof() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert!(ring.ownership_proof(&"foo").is_err());
+
+        for id in 1..=4 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        for x in 0..1000 {
+            let (node, range) = ring.ownership_proof(&x).unwrap();
+            assert_eq!(node.index, ring.owner_index(&x).unwrap());
+            assert!(range.contains(&ring.key(&x)));
+            assert!(!node.prev().owned_range().contains(&ring.key(&x)));
+        }
+
+        // A node's data hashes to exactly its key, which it owns.
+        for node in ring.nodes() {
+            let (owner, range) = ring.ownership_proof(node.data()).unwrap();
+            assert_eq!(owner.index, node.index);
+            assert!(range.contains(node.key()));
+            assert!(!owner.next().owned_range().contains(node.key()));
+        }
+    }
+
+    #[test]
+    fn get_with_jitter() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert!(ring.get_with_jitter(&"foo", 0, 0.1).is_err());
+
+        for id in 1..=4 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        let owner = ring.owner_index(&"foo").unwrap();
+        let replica = ring.get_by_index(owner).unwrap().next().index;
+
+        let mut jittered = 0;
+        for epoch in 0..10_000 {
+            let index = ring.get_with_jitter(&"foo", epoch, 0.1).unwrap().index;
+            assert_eq!(
+                ring.get_with_jitter(&"foo", epoch, 0.1).unwrap().index,
+                index
+            );
+
+            if index == replica {
+                jittered += 1;
+            } else {
+                assert_eq!(index, owner);
+            }
+        }
+
+        assert!((jittered as f64 / 10_000.0 - 0.1).abs() < 0.02);
+
+        // No jitter always routes to the owner.
+        for epoch in 0..100 {
+            assert_eq!(
+                ring.get_with_jitter(&"foo", epoch, 0.0).unwrap().index,
+                owner
+            );
+        }
+    }
+
+    #[test]
+    fn get_tiered() {
+        let zone_of = |node: &VNode| (node.id % 2) as u8;
+
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert!(ring.get_tiered(&"foo", zone_of, &[0, 1]).is_err());
+
+        for id in 1..=8 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        for x in 0..100 {
+            let expected = |zone: u8| {
+                *ring
+                    .failover_order(&x)
+                    .into_iter()
+                    .find(|node| zone_of(node) == zone)
+                    .unwrap()
+            };
+
+            let node = ring.get_tiered(&x, zone_of, &[1, 0]).unwrap();
+            assert_eq!(*node.data(), expected(1));
+
+            // Falls through to the next tier if a zone has no nodes.
+            let node = ring.get_tiered(&x, zone_of, &[2, 0]).unwrap();
+            assert_eq!(*node.data(), expected(0));
+
+            // Falls back to the owner if no tier has any nodes.
+            let node = ring.get_tiered(&x, zone_of, &[2, 3]).unwrap();
+            assert_eq!(node.index, ring.owner_index(&x).unwrap());
+        }
+    }
+
+    #[test]
+    fn with_observer() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        for id in 1..=4 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        let owners = (0..10)
+            .map(|x| ring.owner_index(&x).unwrap())
+            .collect::<Vec<_>>();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let ring = ring.with_observer({
+            let events = events.clone();
+            Arc::new(move |event| events.lock().unwrap().push(event))
+        });
+
+        for x in 0..10 {
+            ring.get_by_hash(&x).unwrap();
+        }
+
+        let taken = core::mem::take(&mut *events.lock().unwrap());
+        assert_eq!(taken.len(), 10);
+
+        for (x, event) in taken.into_iter().enumerate() {
+            assert_eq!(event.key, ring.key(&(x as i32)));
+            assert_eq!(event.index, owners[x]);
+            assert_eq!(event.hops, 0);
+        }
+
+        // Batched lookups notify the observer of every key.
+        let keys = (0..10).collect::<Vec<i32>>();
+        assert_eq!(ring.owners_of(&keys), owners);
+
+        let taken = core::mem::take(&mut *events.lock().unwrap());
+        assert_eq!(taken.len(), 10);
+
+        for (x, event) in taken.into_iter().enumerate() {
+            assert_eq!(event.key, ring.key(&(x as i32)));
+            assert_eq!(event.index, owners[x]);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn with_observer_hops() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let ring = ring_with_keys(vec![(10, "a"), (20, "b"), (30, "c")]).with_observer({
+            let events = events.clone();
+            Arc::new(move |event| events.lock().unwrap().push(event))
+        });
+
+        let key = ring.key(&"foo");
+        let owner = ring.get_by_key(&key).unwrap();
+        let excluded = HashSet::from([*owner.key()]);
+
+        let node = ring.get_excluding(&"foo", &excluded).unwrap();
+        assert_eq!(events.lock().unwrap().as_slice(), &[LookupEvent {
+            key,
+            index: node.index,
+            hops: 1,
+        }]);
+    }
+
+    #[test]
+    fn with_observer_entry_points() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let ring = ring_with_keys(vec![
+            (u64::MAX / 2, "a"),
+            (u64::MAX / 2, "b"),
+            (u64::MAX / 4 * 3, "c"),
+        ])
+        .with_observer({
+            let events = events.clone();
+            Arc::new(move |event| events.lock().unwrap().push(event))
+        });
+
+        let router = ring.thread_local_router();
+        let mut caching = ring.caching_router(4);
+
+        for x in 0..100 {
+            let indices = [
+                ring.get_stable(&x).unwrap().index,
+                ring.get_fair(&x).unwrap().index,
+                router.route(&x).unwrap(),
+                caching.route(&ring, &x).unwrap(),
+                caching.route(&ring, &x).unwrap(),
+            ];
+
+            // Each lookup is observed once, with the index it returned, even
+            // when a colliding node is picked over the owner.
+            let taken = core::mem::take(&mut *events.lock().unwrap());
+            assert_eq!(
+                taken.iter().map(|event| event.index).collect::<Vec<_>>(),
+                indices
+            );
+            assert!(taken.iter().all(|event| event.key == ring.key(&x)));
+        }
+
+        let ring = ring.require_non_empty().ok().unwrap();
+        let node = ring.get_by_hash(&"foo");
+        assert_eq!(events.lock().unwrap().as_slice(), &[LookupEvent {
+            key: ring.ring().key(&"foo"),
+            index: node.index,
+            hops: 0,
+        }]);
+    }
+
+    #[test]
+    fn version() {
+        let mut ring: HashRing<&str> = HashRing::new();
+        assert_eq!(ring.version(), 0);
+
+        ring.add_node("a").unwrap();
+        ring.add_node("b").unwrap();
+        assert_eq!(ring.version(), 2);
+
+        // Failed mutations and lookups leave the version unchanged.
+        assert!(ring.add_node("a").is_err());
+        assert!(ring.remove_node(&"c").is_err());
+        ring.get_by_hash(&"foo").unwrap();
+        assert_eq!(ring.version(), 2);
+
+        ring.remove_node(&"a").unwrap();
+        assert_eq!(ring.version(), 3);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn physical_node_count() {
+        let mut ring: HashRing<&str> = HashRing::new();
+        assert_eq!(ring.physical_node_count(), 0);
+
+        ring.add_node("a").unwrap();
+        ring.add_node("b").unwrap();
+        assert_eq!(ring.physical_node_count(), ring.len());
+
+        ring.add_weighted_node("c", 100).unwrap();
+        assert_eq!(ring.len(), 102);
+        assert_eq!(ring.physical_node_count(), 3);
+
+        let ring = ring_with_keys(vec![(10, "a"), (20, "b"), (30, "a")]);
+        assert_eq!(ring.physical_node_count(), 2);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn equalize() {
+        let mut ring: HashRing<&str> = HashRing::new();
+        ring.add_node("plain").unwrap();
+        for node in ["a", "b", "c"] {
+            ring.add_weighted_node(node, 10).unwrap();
+        }
+
+        let observed = HashMap::from([("a", 1000), ("b", 200), ("c", 100), ("plain", 500)]);
+
+        // Spread between the most and the least loaded weighted node, assuming
+        // load follows the weights.
+        let spread = |ring: &HashRing<&str>| {
+            let loads = ["a", "b", "c"]
+                .map(|node| observed[node] as f64 * ring.weight_of(&node).unwrap() as f64 / 10.0);
+            loads.iter().copied().fold(f64::MIN, f64::max)
+                - loads.iter().copied().fold(f64::MAX, f64::min)
+        };
+
+        let before = spread(&ring);
+        let old = ring.clone();
+        let plan = ring.equalize(&observed, 20);
+
+        assert!(spread(&ring) < before);
+        assert!(ring.weight_of(&"a").unwrap() < 10);
+        assert!(ring.weight_of(&"c").unwrap() > 10);
+        assert_eq!(ring.weight_of(&"plain"), Some(1));
+        assert_eq!(ring.physical_len(), 4);
+
+        assert!(!plan.is_empty());
+        for x in 0..1000 {
+            let key = ring.key(&x);
+            let from = *old.get_by_hash(&x).unwrap().data();
+            let to = *ring.get_by_hash(&x).unwrap().data();
+
+            match plan.iter().find(|(range, ..)| range.contains(&key)) {
+                Some((_, old_owner, new_owner)) => {
+                    assert_eq!((*old_owner, *new_owner), (Some(from), Some(to)));
+                }
+                None => assert_eq!(from, to),
+            }
+        }
+
+        // Already balanced loads are left alone.
+        let mut ring = old.clone();
+        let observed = HashMap::from([("a", 100), ("b", 100), ("c", 100)]);
+        assert!(ring.equalize(&observed, 20).is_empty());
+        assert!(ring == old);
+    }
+
+    #[test]
+    fn physical_len() {
+        let mut ring: HashRing<&str> = HashRing::new();
+        assert_eq!(ring.physical_len(), 0);
+
+        ring.add_node("a").unwrap();
+        ring.add_node("b").unwrap();
+        ring.add_weighted_node("c", 3).unwrap();
+        ring.add_weighted_node("d", 5).unwrap();
+        assert_eq!(ring.len(), 10);
+        assert_eq!(ring.physical_len(), 4);
+
+        ring.remove_node(&"d").unwrap();
+        assert_eq!(ring.len(), 5);
+        assert_eq!(ring.physical_len(), 3);
+
+        ring.retain(|node| *node != "c");
+        assert_eq!(ring.physical_len(), 2);
+
+        ring.clear();
+        assert_eq!(ring.physical_len(), 0);
+    }
+
+    #[test]
+    fn iter_take() {
+        let ring = ring_with_keys(vec![(10, "a"), (20, "b"), (30, "c"), (40, "d")]);
+
+        fn data(iter: Iter<'_, &'static str, DefaultHashBuilder>) -> Vec<&'static str> {
+            iter.map(|node| *node.data()).collect()
+        }
+
+        assert_eq!(data(ring.iter_take(25, 2)), vec!["c", "d"]);
+        assert_eq!(data(ring.iter_take(35, 3)), vec!["d", "a", "b"]);
+        assert_eq!(data(ring.iter_take(None, 0)), Vec::<&str>::new());
+        assert_eq!(data(ring.iter_take(35, 10)), vec!["d", "a", "b", "c"]);
+        assert_eq!(ring.iter_take(35, 3).size_hint(), (3, Some(3)));
+
+        // Walking from the back starts at the last of the `n` nodes.
+        assert_eq!(
+            ring.iter_take(35, 3)
+                .rev()
+                .map(|node| *node.data())
+                .collect::<Vec<_>>(),
+            vec!["b", "a", "d"]
+        );
+
+        let ring: HashRing<&str> = HashRing::new();
+        assert_eq!(ring.iter_take(None, 3).count(), 0);
+    }
+
+    #[test]
+    fn distance_to() {
+        let ring = ring_with_keys(vec![(10, "a"), (50, "b"), (u64::MAX - 5, "c")]);
+        let [a, b, c] = [0, 1, 2].map(|index| NodeRef { ring: &ring, index });
+
+        assert_eq!(a.distance_to(&a), 0);
+        assert_eq!(a.distance_to(&b), 40);
+        assert_eq!(b.distance_to(&a), u64::MAX - 39);
+        assert_eq!(c.distance_to(&a), 16);
+
+        // Distances to the next node add up to the whole keyspace.
+        let total = ring
+            .nodes()
+            .map(|node| node.distance_to(&node.next()))
+            .fold(0u64, u64::wrapping_add);
+        assert_eq!(total, 0);
+    }
+
+    #[test]
+    fn step() {
+        let ring = ring_with_keys(vec![(10, "a"), (20, "b"), (30, "c"), (40, "d")]);
+        let node = ring.get_by_index(1).unwrap();
+
+        assert_eq!(node.step(0).index, 1);
+        assert_eq!(node.step(1).index, node.next().index);
+        assert_eq!(node.step(-1).index, node.prev().index);
+        assert_eq!(node.step(3).index, 0);
+        assert_eq!(node.step(-2).index, 3);
+        assert_eq!(node.step(9).index, 2);
+        assert_eq!(node.step(-9).index, 0);
+        assert_eq!(node.step(isize::MAX).index, 0);
+        assert_eq!(node.step(isize::MIN).index, 1);
+
+        for k in 0..8 {
+            let chained = (0..k).fold(node.step(0), |node, _| node.next());
+            assert_eq!(node.step(k as isize).index, chained.index);
+        }
+    }
+
+    #[test]
+    fn into_iter() {
+        let ring: HashRing<String> = HashRing::new();
+        assert_eq!(ring.into_iter().count(), 0);
+
+        let mut ring = ring_with_keys(vec![(30, "c".to_owned()), (10, "a".to_owned())]);
+        ring.add_weighted_node("w".to_owned(), 10).unwrap();
+
+        let nodes = ring.into_iter().collect::<Vec<_>>();
+        assert_eq!(nodes.len(), 3);
+        assert!(nodes.contains(&"w".to_owned()));
+
+        let ring = ring_with_keys(vec![(10, "a".to_owned()), (30, "c".to_owned())]);
+        let mut nodes = Vec::new();
+        for node in ring {
+            nodes.push(node);
+        }
+        assert_eq!(nodes, vec!["a", "c"]);
+    }
+
+    #[test]
+    fn shrink_to_fit() {
+        let mut ring: HashRing<u32> = HashRing::with_capacity(100);
+        ring.add_node(1).unwrap();
+        ring.add_node(2).unwrap();
+        assert!(ring.data.capacity() >= 100);
+
+        ring.shrink_to_fit();
+        assert_eq!(ring.data.capacity(), 2);
+        assert_eq!(ring.len(), 2);
+        assert_eq!(ring.version(), 2);
+    }
+
+    #[test]
+    fn get_owned() {
+        let mut ring: HashRing<String> = HashRing::new();
+        assert_eq!(ring.get_owned(&"foo"), Err(Error::NodeNotFound));
+
+        ring.add_node("a".to_owned()).unwrap();
+        ring.add_node("b".to_owned()).unwrap();
+
+        let node = ring.get_owned(&"foo").unwrap();
+        assert_eq!(&node, ring.get_by_hash(&"foo").unwrap().data());
+
+        ring.remove_node(&node).unwrap();
+        assert_ne!(ring.get_owned(&"foo").unwrap(), node);
+    }
+
+    #[test]
+    fn key_collision() {
+        let mut ring: HashRing<&str> = HashRing::new();
+        ring.data = vec![Node::new(ring.key(&"b"), "a")];
+
+        assert_eq!(ring.add_node_checked("b"), Err(Error::KeyCollision));
+        assert_eq!(ring.add_node("b"), Err(Error::DuplicateNode));
+        assert!(ring.add_node_checked("a").is_ok());
+
+        ring.data = vec![Node::new(ring.key(&"b"), "b")];
+        assert_eq!(ring.add_node_checked("b"), Err(Error::DuplicateNode));
+    }
+
+    #[test]
+    fn weighted_nodes() {
+        let mut ring: HashRing<&str> = HashRing::new();
+
+        ring.add_weighted_node("small", 100).unwrap();
+        ring.add_weighted_node("large", 400).unwrap();
+        ring.add_node("plain").unwrap();
+        assert_eq!(ring.len(), 501);
+
+        assert_eq!(
+            ring.add_weighted_node("small", 10),
+            Err(Error::DuplicateNode)
+        );
+        assert_eq!(
+            ring.add_weighted_node("plain", 10),
+            Err(Error::DuplicateNode)
+        );
+
+        assert_eq!(ring.weight_of(&"small"), Some(100));
+        assert_eq!(ring.weight_of(&"large"), Some(400));
+        assert_eq!(ring.weight_of(&"plain"), Some(1));
+        assert_eq!(ring.weight_of(&"missing"), None);
+        assert_eq!(ring.verify_keys(), Ok(()));
+
+        let large = (0..10_000)
+            .filter(|x| ring.get_by_hash(x).unwrap().data() == &"large")
+            .count();
+        assert!((large as f64 / 10_000.0 - 0.8).abs() < 0.05);
+
+        ring.remove_node(&"large").unwrap();
+        assert_eq!(ring.len(), 101);
+        assert_eq!(ring.weight_of(&"large"), None);
+        assert_eq!(ring.remove_node(&"large"), Err(Error::NodeNotFound));
+
+        ring.remove_node(&"plain").unwrap();
+        assert!((0..1000).all(|x| ring.get_by_hash(&x).unwrap().data() == &"small"));
+    }
+
+    #[test]
+    fn weighted_collisions() {
+        let mut ring: HashRing<&str> = HashRing::new();
+
+        // A point colliding with another node adds nothing.
+        ring.add_node_unchecked("x");
+        ring.data[0].key = ring.key(&("w", 1u32));
+        assert_eq!(ring.add_weighted_node("w", 3), Err(Error::KeyCollision));
+        assert_eq!(ring.len(), 1);
+        assert_eq!(ring.weight_of(&"w"), None);
+
+        // Another node at a weighted node's plain key doesn't change its
+        // weight, and isn't removed along with it.
+        let mut ring: HashRing<&str> = HashRing::new();
+        ring.add_weighted_node("w", 3).unwrap();
+        ring.add_node_unchecked("y");
+        let index = ring.data.len() - 1;
+        ring.data[index].key = ring.key(&"w");
+        ring.sort();
+
+        assert_eq!(ring.weight_of(&"w"), Some(3));
+        assert_eq!(ring.weight_of(&"y"), None);
+
+        ring.remove_node(&"w").unwrap();
+        assert_eq!(ring.len(), 1);
+        assert_eq!(ring.get_by_hash(&"foo").unwrap().data(), &"y");
+        assert!(ring.weights.is_empty());
+    }
+
+    #[test]
+    fn build_hasher() {
+        let hash_builder = ahash::RandomState::with_seeds(1, 2, 3, 4);
+
+        let mut ring = HashRing::with_hasher(hash_builder.clone());
+        for id in 1..=4 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        assert_eq!(ring.key(&"foo"), hash_builder.hash_one("foo"));
+        assert_eq!(ring.verify_keys(), Ok(()));
+
+        for x in 0..100 {
+            let node = ring.get_by_hash(&x).unwrap();
+            assert!(node.owned_range().contains(&hash_builder.hash_one(x)));
+        }
+    }
+
+    #[test]
+    fn get_n() {
+        let mut ring: HashRing<&str> = HashRing::new();
+        assert!(ring.get_n(&"foo", 3).is_err());
+
+        ring.add_weighted_node("a", 50).unwrap();
+        ring.add_weighted_node("b", 50).unwrap();
+        ring.add_node("c").unwrap();
+        ring.add_node("d").unwrap();
+
+        for x in 0..100 {
+            let replicas = ring.get_n(&x, 3).unwrap();
+            assert_eq!(replicas.len(), 3);
+            assert_eq!(replicas[0].index, ring.owner_index(&x).unwrap());
+
+            let mut data = replicas.iter().map(|node| *node.data()).collect::<Vec<_>>();
+            data.sort();
+            data.dedup();
+            assert_eq!(data.len(), 3);
+
+            // Fewer distinct nodes than requested.
+            assert_eq!(ring.get_n(&x, 10).unwrap().len(), 4);
+        }
+
+        assert!(ring.get_n(&"foo", 0).unwrap().is_empty());
+    }
+
+    #[cfg(feature = "xxhash")]
+    #[test]
+    fn xxhash() {
+        let mut ring = HashRing::with_hasher(XxHashBuilder::with_seed(42));
+        for id in 1..=4 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        let mut hasher = twox_hash::XxHash64::with_seed(42);
+        "foo".hash(&mut hasher);
+        assert_eq!(ring.key(&"foo"), hasher.finish());
+
+        assert_ne!(
+            HashRing::<VNode, _>::with_hasher(XxHashBuilder::default()).key(&"foo"),
+            ring.key(&"foo")
+        );
+
+        for x in 0..100 {
+            let node = ring.get_by_hash(&x).unwrap();
+            assert!(node.owned_range().contains(&ring.key(&x)));
+        }
+    }
+
+    #[test]
+    fn contains_node() {
+        let mut ring: HashRing<&str> = HashRing::new();
+        assert!(!ring.contains_node(&"a"));
+
+        ring.add_node("a").unwrap();
+        ring.add_weighted_node("b", 3).unwrap();
+
+        assert!(ring.contains_node(&"a"));
+        assert!(ring.contains_node(&"b"));
+        assert!(!ring.contains_node(&"c"));
+
+        ring.remove_node(&"b").unwrap();
+        assert!(!ring.contains_node(&"b"));
+    }
+
+    #[test]
+    fn replicas_iter() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert!(ring.replicas_iter(&"foo").next().is_none());
+
+        for id in 1..=4 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        for x in 0..100 {
+            let mut indices = [usize::MAX; 4];
+            let mut count = 0;
+
+            for (slot, node) in indices.iter_mut().zip(ring.replicas_iter(&x)) {
+                *slot = node.index;
+                count += 1;
+            }
+
+            assert_eq!(count, 4);
+            assert_eq!(ring.replicas_iter(&x).count(), 4);
+            assert_eq!(indices[0], ring.owner_index(&x).unwrap());
+
+            indices.sort_unstable();
+            assert_eq!(indices, [0, 1, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn clear() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        for id in 1..=8 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        let capacity = ring.data.capacity();
+        let version = ring.version();
+
+        ring.clear();
+        assert!(ring.is_empty());
+        assert!(ring.get_by_hash(&"foo").is_err());
+        assert_eq!(ring.data.capacity(), capacity);
+        assert!(ring.version() > version);
+
+        ring.add_node(VNode::new("127.0.0.1", 1024, 1)).unwrap();
+        assert_eq!(ring.len(), 1);
+    }
+
+    #[test]
+    fn with_capacity() {
+        let mut ring: HashRing<u32> = HashRing::with_capacity(64);
+        assert!(ring.is_empty());
+        assert!(ring.data.capacity() >= 64);
+
+        let ptr = ring.data.as_ptr();
+        for node in 0..64 {
+            ring.add_node(node).unwrap();
+        }
+        assert_eq!(ring.data.as_ptr(), ptr);
+
+        let ring: HashRing<u32, _> = HashRing::with_capacity_and_hasher(16, DefaultHashBuilder);
+        assert!(ring.data.capacity() >= 16);
+    }
+
+    #[test]
+    fn read_preference() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert!(ring.read_preference(&"foo", 3).is_empty());
+
+        for id in 1..=6 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        let mut firsts = HashSet::new();
+        for x in 0..100 {
+            let mut expected = ring
+                .replicas_iter(&x)
+                .take(3)
+                .map(|node| node.index)
+                .collect::<Vec<_>>();
+
+            let mut nodes = ring
+                .read_preference(&x, 3)
+                .iter()
+                .map(|node| node.index)
+                .collect::<Vec<_>>();
+
+            // Same order up to the rotation.
+            let first = expected
+                .iter()
+                .position(|index| *index == nodes[0])
+                .unwrap();
+            expected.rotate_left(first);
+            assert_eq!(nodes, expected);
+
+            firsts.insert(first);
+
+            nodes.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(nodes, expected);
+        }
+
+        // Every replica is read first for some keys.
+        assert_eq!(firsts.len(), 3);
+    }
+
+    #[test]
+    fn from_iter_and_extend() {
+        let nodes = (1..=6)
+            .map(|id| VNode::new("127.0.0.1", 1024, id))
+            .collect::<Vec<_>>();
+
+        let mut expected: HashRing<VNode> = HashRing::new();
+        for node in &nodes {
+            expected.add_node(*node).unwrap();
+        }
+
+        let ring: HashRing<VNode> = nodes.iter().copied().collect();
+        assert_eq!(ring.fingerprint(), expected.fingerprint());
+
+        // Duplicates, both within the batch and with existing nodes, are
+        // ignored.
+        let mut ring: HashRing<VNode> = nodes[..3].iter().copied().collect();
+        ring.extend(nodes[1..].iter().chain(&nodes[4..]).copied());
+        assert_eq!(ring.len(), 6);
+        assert_eq!(ring.fingerprint(), expected.fingerprint());
+
+        for x in 0..100 {
+            assert_eq!(
+                ring.get_by_hash(&x).unwrap().data(),
+                expected.get_by_hash(&x).unwrap().data()
+            );
+        }
+
+        // Nodes colliding with a different node are dropped.
+        let mut ring: HashRing<&str> = HashRing::new();
+        ring.data = vec![Node::new(ring.key(&"b"), "a")];
+        ring.extend(["b", "c"]);
+        assert_eq!(ring.len(), 2);
+        assert!(ring.iter(None).all(|node| node.data() != &"b"));
+    }
+
+    #[test]
+    fn seeded_hasher() {
+        let ring: HashRing<&str, _> = HashRing::with_hasher(SeededHashBuilder::new(0, 0));
+        assert_eq!(ring.key(&"foo"), HashRing::<&str>::new().key(&"foo"));
+
+        let seeded: HashRing<&str, _> = HashRing::with_hasher(SeededHashBuilder::new(1, 2));
+        assert_ne!(seeded.key(&"foo"), ring.key(&"foo"));
+        assert_eq!(seeded.key(&"foo"), seeded.clone().key(&"foo"));
+
+        #[cfg(feature = "std")]
+        {
+            let random1: HashRing<&str, _> =
+                HashRing::with_hasher(SeededHashBuilder::from_entropy());
+            let random2: HashRing<&str, _> =
+                HashRing::with_hasher(SeededHashBuilder::from_entropy());
+            assert_ne!(random1.key(&"foo"), random2.key(&"foo"));
+        }
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn blake3() {
+        let mut ring: HashRing<u32, _> = HashRing::with_hasher(Blake3HashBuilder);
+
+        // The first 16 bytes of the BLAKE3 digest of the empty input.
+        assert_eq!(
+            ring.key(&RawBytes(b"")),
+            0xaf13_49b9_f5f9_a1a6_a040_4dea_36dc_c949
+        );
+
+        for node in 0..1000 {
+            ring.add_node(node).unwrap();
+        }
+
+        let total = ring
+            .nodes()
+            .map(|node| node.owned_range().size() / 1000)
+            .sum::<u128>();
+        assert!(total > u128::MAX / 1000 - 1000);
+
+        // Stats are computed over the whole 128-bit keyspace.
+        assert_eq!(ring.mean_partition_size(), u128::MAX / 1000);
+
+        let fractions = (0..1000)
+            .map(|node| ring.ownership_fraction(&node))
+            .sum::<f64>();
+        assert!((fractions - 1.0).abs() < 1e-9);
+        assert_eq!(ring.ownership_fraction(&1000), 0.0);
+
+        let histogram = ring.position_histogram(10);
+        assert_eq!(histogram.iter().sum::<usize>(), 1000);
+        assert!(histogram.iter().all(|&count| count > 50));
+    }
+
+    #[cfg(feature = "crc32")]
+    #[test]
+    fn crc32() {
+        let ring: HashRing<&str, _> = HashRing::with_hasher(Crc32HashBuilder);
+
+        // The standard CRC32 check value.
+        assert_eq!(ring.key(&RawBytes(b"123456789")), 0xcbf4_3926);
+
+        let mut ring = ring;
+        ring.data = vec![
+            Node::new(0x4000_0000, "a"),
+            Node::new(0x8000_0000, "b"),
+            Node::new(0xc000_0000, "c"),
+            Node::new(0xffff_ffff, "d"),
+        ];
+
+        assert_eq!(
+            ring.get_by_hash(&RawBytes(b"123456789")).unwrap().data(),
+            &"d"
+        );
+        assert_eq!(ring.get_by_key(&0).unwrap().data(), &"a");
+        assert_eq!(ring.get_by_key(&0x8000_0001).unwrap().data(), &"c");
+    }
+
+    #[test]
+    fn nodes() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert_eq!(ring.nodes().count(), 0);
+
+        for id in 1..=6 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        let nodes = ring.nodes();
+        assert_eq!(nodes.len(), 6);
+        assert_eq!(nodes.map(|node| node.index).collect::<Vec<_>>(), vec![
+            0, 1, 2, 3, 4, 5
+        ]);
+        assert_eq!(
+            ring.nodes().map(|node| *node.key()).collect::<Vec<_>>(),
+            ring.fingerprint()
+        );
+    }
+
+    #[test]
+    fn ranges() {
+        let ring = ring_with_keys(vec![(10, "a"), (20, "b"), (30, "c")]);
+
+        let ranges = ring
+            .ranges()
+            .map(|(node, range)| (*node.data(), range))
+            .collect::<Vec<_>>();
+
+        assert_eq!(ranges, vec![
+            ("a", KeyRange::new(10, 20)),
+            ("b", KeyRange::new(20, 30)),
+            ("c", KeyRange::new(30, 10)),
+        ]);
+
+        let ring = ring_with_keys(vec![(10, "a")]);
+        let (_, range) = ring.ranges().next().unwrap();
+        assert_eq!(range, KeyRange::new(10, 10));
+        assert_eq!(range.size(), u64::MAX);
+
+        let ring: HashRing<&str> = HashRing::new();
+        assert_eq!(ring.ranges().count(), 0);
+    }
+
+    #[test]
+    fn iter_rev() {
+        let ring: HashRing<&str> = HashRing::new();
+        assert!(ring.iter(None).next_back().is_none());
+
+        let ring = ring_with_keys(vec![(10, "a")]);
+        assert_eq!(
+            ring.iter(None)
+                .rev()
+                .map(|node| *node.data())
+                .collect::<Vec<_>>(),
+            vec!["a"]
+        );
+
+        let ring = ring_with_keys(vec![(10, "a"), (20, "b"), (30, "c"), (40, "d")]);
+        fn data<'a>(
+            iter: impl Iterator<Item = NodeRef<'a, &'static str, DefaultHashBuilder>>,
+        ) -> Vec<&'static str> {
+            iter.map(|node| *node.data()).collect()
+        }
+
+        assert_eq!(data(ring.iter(15).rev()), vec!["a", "d", "c", "b"]);
+        assert_eq!(data(ring.iter(15)), vec!["b", "c", "d", "a"]);
+
+        // Both ends meet without repeating nodes.
+        let mut iter = ring.iter(15);
+        assert_eq!(iter.next().unwrap().data(), &"b");
+        assert_eq!(iter.next_back().unwrap().data(), &"a");
+        assert_eq!(iter.next_back().unwrap().data(), &"d");
+        assert_eq!(iter.next().unwrap().data(), &"c");
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+    }
+
+    #[test]
+    fn update_node() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Server {
+            name: &'static str,
+            load: u32,
+        }
+
+        // Only the name determines the position on the ring.
+        impl Hash for Server {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.name.hash(state);
+            }
+        }
+
+        let server = |name, load| Server { name, load };
+
+        let mut ring = HashRing::new();
+        for name in ["a", "b", "c"] {
+            ring.add_node(server(name, 0)).unwrap();
+        }
+
+        let fingerprint = ring.fingerprint();
+
+        ring.update_node(&server("b", 0), server("b", 42)).unwrap();
+        assert_eq!(ring.fingerprint(), fingerprint);
+        assert_eq!(ring.node(&server("b", 0)).unwrap().data().load, 42);
+
+        assert_eq!(
+            ring.update_node(&server("b", 0), server("d", 0)),
+            Err(Error::KeyMismatch)
+        );
+        assert_eq!(
+            ring.update_node(&server("d", 0), server("d", 1)),
+            Err(Error::NodeNotFound)
+        );
+
+        ring.add_weighted_node(server("w", 0), 4).unwrap();
+        ring.update_node(&server("w", 0), server("w", 7)).unwrap();
+
+        let loads = ring
+            .iter(None)
+            .filter(|node| node.data().name == "w")
+            .map(|node| node.data().load)
+            .collect::<Vec<_>>();
+        assert_eq!(loads, vec![7; 4]);
+        assert_eq!(ring.weight_of(&server("w", 7)), Some(4));
+
+        assert_eq!(
+            ring.update_node(&server("w", 7), server("x", 7)),
+            Err(Error::KeyMismatch)
+        );
+    }
+
+    #[test]
+    fn data_mut() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Server {
+            name: &'static str,
+            requests: u32,
+        }
+
+        impl Hash for Server {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                self.name.hash(state);
+            }
+        }
+
+        let server = |name| Server { name, requests: 0 };
+
+        let mut ring = HashRing::new();
+        assert_eq!(
+            ring.get_by_hash_mut(&"foo").unwrap_err(),
+            Error::NodeNotFound
+        );
+
+        for name in ["a", "b", "c"] {
+            ring.add_node(server(name)).unwrap();
+        }
+
+        ring.data_mut(&server("b")).unwrap().requests += 1;
+        assert_eq!(ring.node(&server("b")).unwrap().data().requests, 1);
+        assert_eq!(
+            ring.data_mut(&server("d")).unwrap_err(),
+            Error::NodeNotFound
+        );
+
+        for x in 0..10 {
+            ring.get_by_hash_mut(&x).unwrap().requests += 1;
+        }
+
+        let total = ring.nodes().map(|node| node.data().requests).sum::<u32>();
+        assert_eq!(total, 11);
+        assert_eq!(ring.verify_keys(), Ok(()));
+
+        ring.add_weighted_node(server("w"), 3).unwrap();
+        assert_eq!(
+            ring.data_mut(&server("w")).unwrap_err(),
+            Error::NodeNotFound
+        );
+    }
+
+    #[test]
+    fn retain() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        for id in 1..=8 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+        ring.add_weighted_node(VNode::new("127.0.0.2", 1024, 9), 4)
+            .unwrap();
+
+        ring.retain(|node| node.id % 3 != 0);
+
+        assert_eq!(ring.len(), 6);
+        assert!(ring.nodes().all(|node| node.data().id % 3 != 0));
+        assert!(ring.fingerprint().windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(ring.verify_keys(), Ok(()));
+
+        for x in 0..100 {
+            assert_ne!(ring.get_by_hash(&x).unwrap().data().id % 3, 0);
+        }
+    }
+
+    #[test]
+    fn migration() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        let node1 = VNode::new("127.0.0.1", 1024, 1);
+
+        let (_, range) = ring.add_node_with_migration(node1).unwrap();
+        assert_eq!(range.start, range.end);
+
+        for id in 2..=6 {
+            let node = VNode::new("127.0.0.1", 1024, id);
+            let owners = (0..1000)
+                .map(|x| ring.owner_index(&x).unwrap())
+                .collect::<Vec<_>>();
+            let before = ring.clone();
+
+            let (index, range) = ring.add_node_with_migration(node).unwrap();
+            assert_eq!(range.end, ring.key(&node) + 1);
+
+            // Exactly the keys in the range move, all to the new node.
+            for x in 0..1000 {
+                let owner = ring.get_by_hash(&x).unwrap();
+                if range.contains(&ring.key(&x)) {
+                    assert_eq!(owner.index, index);
+                } else {
+                    assert_eq!(
+                        owner.data(),
+                        before.get_by_index(owners[x as usize]).unwrap().data()
+                    );
+                }
+            }
+        }
+
+        assert_eq!(
+            ring.add_node_with_migration(node1),
+            Err(Error::DuplicateNode)
+        );
+
+        let node3 = VNode::new("127.0.0.1", 1024, 3);
+        let successor = *ring.node(&node3).unwrap().next().data();
+        let ranges = ring.remove_node_with_migration(&node3).unwrap();
+        assert_eq!(ranges.len(), 1);
+        let range = &ranges[0];
+        assert_eq!(range.end, ring.key(&node3) + 1);
+
+        for x in 0..1000 {
+            if range.contains(&ring.key(&x)) {
+                assert_eq!(ring.get_by_hash(&x).unwrap().data(), &successor);
+            }
+        }
+
+        assert_eq!(
+            ring.remove_node_with_migration(&node3),
+            Err(Error::NodeNotFound)
+        );
+
+        let weighted = VNode::new("127.0.0.2", 1024, 9);
+        ring.add_weighted_node(weighted, 4).unwrap();
+
+        let owned = (0..1000)
+            .map(|x| ring.get_by_hash(&x).unwrap().data() == &weighted)
+            .collect::<Vec<_>>();
+        let ranges = ring.remove_node_with_migration(&weighted).unwrap();
+        assert!(!ring.contains_node(&weighted));
+
+        for x in 0..1000 {
+            let key = ring.key(&x);
+            assert_eq!(
+                ranges.iter().any(|range| range.contains(&key)),
+                owned[x as usize]
+            );
+        }
+    }
+
+    #[test]
+    fn drain_range() {
+        let nodes = vec![(10, "a"), (20, "b"), (30, "c"), (40, "d"), (50, "e")];
+
+        let mut ring = ring_with_keys(nodes.clone());
+        assert_eq!(ring.drain_range(&20, &40), vec!["b", "c"]);
+        assert_eq!(ring.fingerprint(), vec![10, 40, 50]);
+        assert_eq!(ring.get_by_key(&25).unwrap().data(), &"d");
+
+        // Wrapping range.
+        let mut ring = ring_with_keys(nodes.clone());
+        assert_eq!(ring.drain_range(&45, &15), vec!["a", "e"]);
+        assert_eq!(ring.fingerprint(), vec![20, 30, 40]);
+
+        // Nothing in range.
+        let mut ring = ring_with_keys(nodes);
+        assert!(ring.drain_range(&11, &19).is_empty());
+        assert_eq!(ring.len(), 5);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn sample_replicas() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(42);
+
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert!(ring.sample_replicas(&"foo", 3, 2, &mut rng).is_empty());
+
+        for id in 1..=6 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        for x in 0..100 {
+            let replicas = ring
+                .iter(ring.key(&x))
+                .take(4)
+                .map(|node| node.index)
+                .collect::<Vec<_>>();
+
+            let sample = ring.sample_replicas(&x, 4, 2, &mut rng);
+            assert_eq!(sample.len(), 2);
+            assert_ne!(sample[0].index, sample[1].index);
+            assert!(sample.iter().all(|node| replicas.contains(&node.index)));
+        }
+
+        assert_eq!(ring.sample_replicas(&"foo", 3, 5, &mut rng).len(), 3);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn sample_by_ownership() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(42);
+
+        let ring: HashRing<&str> = HashRing::new();
+        assert!(ring.sample_by_ownership(&mut rng).is_none());
+
+        // "a" owns half of the keyspace, "b" and "c" a quarter each, and "d"
+        // nothing.
+        let ring = ring_with_keys(vec![
+            (0, "a"),
+            (1 << 62, "b"),
+            (1 << 63, "c"),
+            (1 << 63, "d"),
+        ]);
+
+        let mut counts = HashMap::new();
+        for _ in 0..10_000 {
+            let node = ring.sample_by_ownership(&mut rng).unwrap();
+            *counts.entry(*node.data()).or_insert(0) += 1;
+        }
+
+        let share = |node| counts.get(node).copied().unwrap_or(0) as f64 / 10_000.0;
+        assert!((share(&"a") - 0.5).abs() < 0.02);
+        assert!((share(&"b") - 0.25).abs() < 0.02);
+        assert!((share(&"c") - 0.25).abs() < 0.02);
+        assert_eq!(share(&"d"), 0.0);
+
+        let ring = ring_with_keys(vec![(1 << 62, "a")]);
+        assert_eq!(ring.sample_by_ownership(&mut rng).unwrap().data(), &"a");
+
+        // All nodes sharing a single key.
+        let ring = ring_with_keys(vec![(1 << 62, "a"), (1 << 62, "b"), (1 << 62, "c")]);
+        let owner = ring.get_by_key(&0).unwrap().index;
+        for _ in 0..10 {
+            assert_eq!(ring.sample_by_ownership(&mut rng).unwrap().index, owner);
+        }
+    }
+
+    #[test]
+    fn mean_partition_size() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert_eq!(ring.mean_partition_size(), 0);
+
+        for id in 1..=7 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        let mean = ring.mean_partition_size() as u128;
+        let total = ring
+            .iter(None)
+            .map(|node| node.owned_range().size() as u128)
+            .sum::<u128>();
+
+        assert_eq!(total, u64::MAX as u128);
+        assert!(total - mean * 7 < 7);
+    }
+
+    #[test]
+    fn get_sticky() {
+        let node1 = VNode::new("127.0.0.1", 1024, 1);
+        let node2 = VNode::new("127.0.0.1", 1024, 2);
+        let node3 = VNode::new("127.0.0.1", 1024, 3);
+
+        let mut ring: HashRing<VNode> = HashRing::new();
+        ring.add_node(node1).unwrap();
+        ring.add_node(node2).unwrap();
+        ring.add_node(node3).unwrap();
+
+        let owner = *ring.get_by_hash(&"session").unwrap().data();
+        let other = [node1, node2, node3]
+            .into_iter()
+            .find(|node| *node != owner)
+            .unwrap();
+        let hint = ring.key(&other);
+
+        // No hint.
+        assert_eq!(ring.get_sticky(&"session", None).unwrap().data(), &owner);
+
+        // Valid hint.
+        assert_eq!(
+            ring.get_sticky(&"session", Some(&hint)).unwrap().data(),
+            &other
+        );
+
+        // Stale hint.
+        ring.remove_node(&other).unwrap();
+        let owner = *ring.get_by_hash(&"session").unwrap().data();
+        assert_eq!(
+            ring.get_sticky(&"session", Some(&hint)).unwrap().data(),
+            &owner
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn get_excluding() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        for id in 1..=3 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        let owner = ring.get_by_hash(&"foo").unwrap();
+        let mut excluded = HashSet::new();
+        assert_eq!(
+            ring.get_excluding(&"foo", &excluded).unwrap().index,
+            owner.index
+        );
+
+        excluded.insert(*owner.key());
+        assert_eq!(
+            ring.get_excluding(&"foo", &excluded).unwrap().index,
+            owner.next().index
+        );
+
+        excluded.extend(ring.fingerprint());
+        assert!(matches!(
+            ring.get_excluding(&"foo", &excluded),
+            Err(Error::NodeNotFound)
+        ));
+    }
+
+    #[test]
+    fn with_mixer() {
+        /// Uses integer inputs as keys directly, like a structured input would.
+        #[derive(Clone)]
+        struct IdentityHashBuilder;
+
+        #[derive(Default)]
+        struct IdentityHasher(u64);
+
+        impl Hasher for IdentityHasher {
+            fn finish(&self) -> u64 {
+                self.0
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                for byte in bytes {
+                    self.0 = (self.0 << 8) | *byte as u64;
+                }
+            }
+
+            fn write_u64(&mut self, i: u64) {
+                self.0 = i;
+            }
+        }
+
+        impl BuildHasher for IdentityHashBuilder {
+            type Hasher = IdentityHasher;
+
+            fn build_hasher(&self) -> Self::Hasher {
+                IdentityHasher::default()
+            }
+        }
+
+        fn max_share<S: RingHasher<Key = u64>>(hash_builder: S) -> f64 {
+            let mut ring = HashRing::with_hasher(hash_builder);
+            for node in 0..8u64 {
+                ring.add_node(node).unwrap();
+            }
+
+            let mut counts = [0u32; 8];
+            for key in 0..10_000u64 {
+                counts[ring.owner_index(&key).unwrap()] += 1;
+            }
+
+            *counts.iter().max().unwrap() as f64 / 10_000.0
+        }
+
+        // Sequential node and key IDs all cluster on a single node.
+        assert!(max_share(IdentityHashBuilder) > 0.99);
+
+        // Mixing spreads them out.
+        assert!(max_share(MixedHashBuilder::new(IdentityHashBuilder, fibonacci_mix)) < 0.5);
+
+        let mut ring = HashRing::with_mixer(fibonacci_mix);
+        ring.add_node("a").unwrap();
+        assert_eq!(
+            ring.key(&"a"),
+            fibonacci_mix(HashRing::<&str>::new().key(&"a"))
+        );
+    }
+
+    #[test]
+    fn failover_order() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert!(ring.failover_order(&"foo").is_empty());
+
+        for id in 1..=5 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        let nodes = ring.data.iter().map(|node| &node.data).collect::<Vec<_>>();
+
+        for x in 0..100 {
+            let order = ring.failover_order(&x);
+            let owner = ring.owner_index(&x).unwrap();
+
+            assert_eq!(order.len(), ring.len());
+            assert_eq!(order[0], ring.get_by_hash(&x).unwrap().data());
+
+            let mut rotated = nodes.clone();
+            rotated.rotate_left(owner);
+            assert_eq!(order, rotated);
+        }
+    }
+
+    #[test]
+    fn is_subset_of() {
+        let nodes = (1..=6)
+            .map(|id| VNode::new("127.0.0.1", 1024, id))
+            .collect::<Vec<_>>();
+
+        let mut reference: HashRing<VNode> = HashRing::new();
+        for node in &nodes {
+            reference.add_node(*node).unwrap();
+        }
+
+        let mut subset: HashRing<VNode> = HashRing::new();
+        assert!(subset.is_subset_of(&reference));
+
+        for node in nodes.iter().step_by(2) {
+            subset.add_node(*node).unwrap();
+        }
+        assert!(subset.is_subset_of(&reference));
+        assert!(!reference.is_subset_of(&subset));
+        assert!(reference.is_subset_of(&reference));
+
+        subset.add_node(VNode::new("127.0.0.2", 1024, 1)).unwrap();
+        assert!(!subset.is_subset_of(&reference));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn route_reader() {
+        let keys: [&[u8]; 4] = [b"foo", b"bar", b"", b"a somewhat longer key"];
+
+        let mut buf = Vec::new();
+        for key in keys {
+            buf.extend_from_slice(&(key.len() as u32).to_be_bytes());
+            buf.extend_from_slice(key);
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let current = self.next.take();
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert!(ring.route_reader(buf.as_slice(), |_, _| {}).is_err());
 
-        if let Some(current) = &current {
-            let next = current.next();
+        for id in 1..=3 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
 
-            if next.index != self.start {
-                self.next = Some(next);
-            }
+        let mut routed = Vec::new();
+        ring.route_reader(buf.as_slice(), |index, key| {
+            routed.push((index, key.to_vec()))
+        })
+        .unwrap();
+
+        assert_eq!(routed.len(), keys.len());
+        for ((index, routed_key), key) in routed.into_iter().zip(keys) {
+            assert_eq!(routed_key, key);
+            assert_eq!(index, ring.owner_index(&key).unwrap());
         }
 
-        current
+        // Truncated key.
+        let err = ring
+            .route_reader(&buf[..buf.len() - 1], |_, _| {})
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+        // Oversized length prefix.
+        let mut buf = u32::MAX.to_be_bytes().to_vec();
+        buf.extend_from_slice(b"foo");
+        let err = ring.route_reader(buf.as_slice(), |_, _| {}).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut buf = (MAX_STREAMED_KEY_LEN as u32).to_be_bytes().to_vec();
+        buf.resize(buf.len() + MAX_STREAMED_KEY_LEN, 0);
+        let mut routed = 0;
+        ring.route_reader(buf.as_slice(), |_, _| routed += 1)
+            .unwrap();
+        assert_eq!(routed, 1);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use {
-        super::*,
-        std::{
-            net::{IpAddr, SocketAddr},
-            str::FromStr,
-        },
-    };
+    #[test]
+    fn node_at_fraction() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert!(ring.node_at_fraction(0.5).is_err());
 
-    #[derive(Debug, Copy, Clone, Hash, PartialEq)]
-    struct VNode {
-        id: usize,
-        addr: SocketAddr,
+        for id in 1..=8 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+
+        let start = ring.node_at_fraction(0.0).unwrap().index;
+        let middle = ring.node_at_fraction(0.5).unwrap().index;
+        let end = ring.node_at_fraction(0.99).unwrap().index;
+
+        assert_eq!(start, 0);
+        assert!(middle >= start);
+        // Past the last node, keys wrap around to the first one.
+        assert!(end >= middle || end == 0);
+
+        assert_eq!(
+            ring.node_at_fraction(0.5).unwrap().index,
+            ring.get_by_key(&(u64::MAX / 2 + 1)).unwrap().index
+        );
+        assert_eq!(ring.node_at_fraction(-1.0).unwrap().index, 0);
+        assert_eq!(
+            ring.node_at_fraction(2.0).unwrap().index,
+            ring.get_by_key(&u64::MAX).unwrap().index
+        );
     }
 
-    impl VNode {
-        fn new(ip: &str, port: u16, id: usize) -> Self {
-            let addr = SocketAddr::new(IpAddr::from_str(&ip).unwrap(), port);
-            VNode { id, addr }
+    #[test]
+    fn owned_counts() {
+        let mut ring: HashRing<VNode> = HashRing::new();
+        assert!(ring.owned_counts(0..100).is_empty());
+
+        for id in 1..=4 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
         }
+
+        let counts = ring.owned_counts(0..1000);
+        assert_eq!(counts.len(), 4);
+        assert_eq!(counts.iter().sum::<u64>(), 1000);
+
+        let owner = ring.owner_index(&"foo").unwrap();
+        let counts = ring.owned_counts(["foo"; 3]);
+        assert_eq!(counts[owner], 3);
+        assert_eq!(counts.iter().sum::<u64>(), 3);
     }
 
     #[test]
-    fn add_and_remove_nodes() {
+    fn stable_id() {
+        let node = VNode::new("127.0.0.1", 1024, 1);
+
         let mut ring: HashRing<VNode> = HashRing::new();
+        ring.add_node(node).unwrap();
 
-        assert_eq!(ring.len(), 0);
-        assert!(ring.is_empty());
+        let id = ring.node(&node).unwrap().stable_id();
+        assert_eq!(id, ring.key(&node));
 
-        let vnode1 = VNode::new("127.0.0.1", 1024, 1);
-        let vnode2 = VNode::new("127.0.0.1", 1024, 2);
-        let vnode3 = VNode::new("127.0.0.2", 1024, 1);
+        for id in 2..=10 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
 
-        ring.add_node(vnode1).unwrap();
-        ring.add_node(vnode2).unwrap();
-        ring.add_node(vnode3).unwrap();
-        assert_eq!(ring.len(), 3);
-        assert!(!ring.is_empty());
+        assert_eq!(ring.node(&node).unwrap().stable_id(), id);
+    }
 
-        ring.remove_node(&vnode2).unwrap();
-        assert_eq!(ring.len(), 2);
+    #[test]
+    fn prune_zero_width() {
+        let mut ring = ring_with_keys(vec![(10, "a"), (20, "b"), (20, "c"), (30, "d"), (30, "e")]);
 
-        let vnode4 = VNode::new("127.0.0.2", 1024, 2);
-        let vnode5 = VNode::new("127.0.0.2", 1024, 3);
-        let vnode6 = VNode::new("127.0.0.3", 1024, 1);
+        assert_eq!(ring.prune_zero_width(), vec!["c", "e"]);
+        assert_eq!(ring.fingerprint(), vec![10, 20, 30]);
+        assert_eq!(ring.get_by_key(&15).unwrap().data(), &"b");
+        assert_eq!(ring.get_by_key(&20).unwrap().data(), &"b");
+        assert_eq!(ring.get_by_key(&25).unwrap().data(), &"d");
 
-        ring.add_node(vnode4).unwrap();
-        ring.add_node(vnode5).unwrap();
-        ring.add_node(vnode6).unwrap();
+        assert!(ring.prune_zero_width().is_empty());
 
-        ring.remove_node(&vnode1).unwrap();
-        ring.remove_node(&vnode3).unwrap();
-        ring.remove_node(&vnode6).unwrap();
-        assert_eq!(ring.len(), 2);
+        // All nodes at the same position.
+        let mut ring = ring_with_keys(vec![(10, "a"), (10, "b")]);
+        assert_eq!(ring.prune_zero_width(), vec!["b"]);
+        assert_eq!(ring.get_by_key(&0).unwrap().data(), &"a");
     }
 
     #[test]
-    fn get_nodes() {
+    fn get_weighted_choice() {
+        let weight_of = |node: &VNode| node.id as u32;
+
         let mut ring: HashRing<VNode> = HashRing::new();
+        assert!(ring.get_weighted_choice(&"foo", weight_of, 3).is_err());
 
-        assert!(matches!(
-            ring.get_by_hash(&"foo"),
-            Err(super::Error::NodeNotFound)
-        ));
+        for id in [1, 2, 5] {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
 
-        let vnode1 = VNode::new("127.0.0.1", 1024, 1);
-        let vnode2 = VNode::new("127.0.0.1", 1024, 2);
-        let vnode3 = VNode::new("127.0.0.2", 1024, 1);
-        let vnode4 = VNode::new("127.0.0.2", 1024, 2);
-        let vnode5 = VNode::new("127.0.0.2", 1024, 3);
-        let vnode6 = VNode::new("127.0.0.3", 1024, 1);
+        let mut counts = [0u32; 6];
+        for x in 0..20_000 {
+            let node = ring.get_weighted_choice(&x, weight_of, 3).unwrap();
+            assert_eq!(
+                ring.get_weighted_choice(&x, weight_of, 3).unwrap().index,
+                node.index
+            );
 
-        ring.add_node(vnode1).unwrap();
-        ring.add_node(vnode2).unwrap();
-        ring.add_node(vnode3).unwrap();
-        ring.add_node(vnode4).unwrap();
-        ring.add_node(vnode5).unwrap();
-        ring.add_node(vnode6).unwrap();
+            counts[node.data().id] += 1;
+        }
 
-        assert_eq!(ring.get_by_hash(&"foo").unwrap().data(), &vnode5);
-        assert_eq!(ring.get_by_hash(&"bar").unwrap().data(), &vnode3);
-        assert_eq!(ring.get_by_hash(&"baz").unwrap().data(), &vnode5);
+        let share = |id: usize| counts[id] as f64 / 20_000.0;
+        assert!((share(1) - 1.0 / 8.0).abs() < 0.02);
+        assert!((share(2) - 2.0 / 8.0).abs() < 0.02);
+        assert!((share(5) - 5.0 / 8.0).abs() < 0.02);
 
-        assert_eq!(ring.get_by_hash(&"abc").unwrap().data(), &vnode2);
-        assert_eq!(ring.get_by_hash(&"def").unwrap().data(), &vnode2);
-        assert_eq!(ring.get_by_hash(&"ghi").unwrap().data(), &vnode6);
+        // A fanout of one always picks the owner.
+        for x in 0..100 {
+            assert_eq!(
+                ring.get_weighted_choice(&x, weight_of, 1).unwrap().index,
+                ring.owner_index(&x).unwrap()
+            );
+        }
 
-        assert_eq!(ring.get_by_hash(&"cat").unwrap().data(), &vnode1);
-        assert_eq!(ring.get_by_hash(&"dog").unwrap().data(), &vnode5);
-        assert_eq!(ring.get_by_hash(&"bird").unwrap().data(), &vnode5);
+        // Zero weights fall back to the owner.
+        assert_eq!(
+            ring.get_weighted_choice(&"foo", |_| 0, 3).unwrap().index,
+            ring.owner_index(&"foo").unwrap()
+        );
 
-        // at least each node as a key
-        let mut nodes = vec![0; 6];
-        for x in 0..50_000 {
-            let node = ring.get_by_hash(&x).unwrap();
-            let node = node.data();
-            if vnode1 == *node {
-                nodes[0] += 1;
-            }
-            if vnode2 == *node {
-                nodes[1] += 1;
-            }
-            if vnode3 == *node {
-                nodes[2] += 1;
-            }
-            if vnode4 == *node {
-                nodes[3] += 1;
-            }
-            if vnode5 == *node {
-                nodes[4] += 1;
-            }
-            if vnode6 == *node {
-                nodes[5] += 1;
-            }
+        // The points of a weighted node take up a single candidate slot.
+        let mut ring: HashRing<&str> = HashRing::new();
+        ring.add_weighted_node("a", 100).unwrap();
+        ring.add_node("b").unwrap();
+        ring.add_node("c").unwrap();
+
+        let mut counts = HashMap::new();
+        for x in 0..30_000 {
+            let node = ring.get_weighted_choice(&x, |_| 1, 3).unwrap();
+            *counts.entry(*node.data()).or_insert(0) += 1;
+        }
+
+        for node in ["a", "b", "c"] {
+            assert!((counts[node] as f64 / 30_000.0 - 1.0 / 3.0).abs() < 0.02);
         }
-        println!("{:?}", nodes);
-        assert!(nodes.iter().all(|x| *x != 0));
     }
 
     #[test]
-    fn advanced() {
-        let mut ring: HashRing<VNode> = HashRing::new();
+    fn verify_keys() {
+        let mut ring = HashRing::new();
 
-        let vnode1 = VNode::new("127.0.0.1", 1024, 1);
-        let vnode2 = VNode::new("127.0.0.1", 1024, 2);
+        for node in ["a", "b", "c", "d"] {
+            ring.add_node(node).unwrap();
+        }
 
-        // Ok: no nodes were affected.
-        let result = ring.add_node(vnode1).unwrap();
-        assert_eq!(result, 0);
-        let vnode1_prev_range = ring.get_by_hash(&vnode1).unwrap().range();
+        assert_eq!(ring.verify_keys(), Ok(()));
 
-        // Error: duplicates are not allowed.
-        let result = ring.add_node(vnode1);
-        assert_eq!(result.unwrap_err(), super::Error::DuplicateNode);
+        // Corrupt one stored key without re-sorting.
+        let index = ring.node(&"c").unwrap().index;
+        ring.data[index].key = ring.data[index].key.wrapping_add(1);
+        assert_eq!(ring.verify_keys(), Err(vec![index]));
+    }
 
-        let result = ring.add_node(vnode2).unwrap();
-        assert_eq!(result, 1);
-        let vnode1_curr_range = ring.get_by_hash(&vnode1).unwrap().range();
+    #[test]
+    fn locate_with_headroom() {
+        let ring: HashRing<&str> = HashRing::new();
+        assert!(ring.locate_with_headroom(&"foo").is_err());
 
-        let vnode2_curr_range = ring.get_by_hash(&vnode2).unwrap().range();
+        let key = ring.key(&"foo");
+        let ring = ring_with_keys(vec![
+            (key.wrapping_add(10), "a"),
+            (key.wrapping_add(1 << 63), "b"),
+        ]);
 
-        assert_eq!(vnode1_prev_range.start, vnode1_curr_range.start);
-        assert_eq!(vnode1_prev_range.end, vnode2_curr_range.end);
-        assert_eq!(vnode1_curr_range.end, vnode2_curr_range.start);
+        let (node, headroom) = ring.locate_with_headroom(&"foo").unwrap();
+        assert_eq!(node.data(), &"a");
+        assert_eq!(headroom, 10);
+
+        // Past the last node, the distance wraps around to the first one.
+        let key = ring.key(&"bar");
+        let ring = ring_with_keys(vec![(key.wrapping_sub(10), "a")]);
+        assert_eq!(ring.locate_with_headroom(&"bar").unwrap().1, u64::MAX - 9);
     }
 
     #[test]
-    fn node_ref() {
-        let mut ring: HashRing<VNode> = HashRing::new();
-
-        let vnode1 = VNode::new("127.0.0.1", 1024, 1);
-        let vnode2 = VNode::new("127.0.0.1", 1024, 2);
-
-        assert!(matches!(
-            ring.get_by_hash(&vnode1),
-            Err(super::Error::NodeNotFound)
-        ));
+    fn get_by_key_interpolated() {
+        let mut ring: HashRing<u32> = HashRing::new();
+        assert!(ring.get_by_key_interpolated(&0).is_err());
 
-        ring.add_node(vnode1).unwrap();
+        for node in 0..1000 {
+            ring.add_node(node).unwrap();
+        }
 
-        let node_ref = ring.get_by_hash(&vnode1).unwrap();
-        assert_eq!(node_ref.data(), &vnode1);
-        assert_eq!(node_ref.index, 0);
-        assert_eq!(node_ref.prev().index, 0);
-        assert_eq!(node_ref.next().index, 0);
+        let keys = (0..10_000)
+            .map(|x| ring.key(&x))
+            .chain(ring.fingerprint())
+            .chain([0, 1, u64::MAX - 1, u64::MAX]);
 
-        ring.add_node(vnode2).unwrap();
+        for key in keys {
+            assert_eq!(
+                ring.get_by_key_interpolated(&key).unwrap().index,
+                ring.get_by_key(&key).unwrap().index
+            );
+        }
 
-        let node_ref = ring.get_by_hash(&vnode2).unwrap();
-        assert_eq!(node_ref.data(), &vnode2);
-        assert_eq!(node_ref.prev().data(), &vnode1);
-        assert_eq!(node_ref.next().data(), &vnode1);
+        // Skewed keys fall back to binary search.
+        let ring = ring_with_keys((0..64).map(|x| (1 << x, x)).collect());
+        for key in (0..64).flat_map(|x| [1 << x, (1 << x) + 1]) {
+            assert_eq!(
+                ring.get_by_key_interpolated(&key).unwrap().index,
+                ring.get_by_key(&key).unwrap().index
+            );
+        }
     }
 
     #[test]
-    fn node_range() {
-        // One node.
-        {
-            let mut ring: HashRing<VNode> = HashRing::new();
-            let node = VNode::new("127.0.0.1", 1024, 1);
-            let node_key = ring.key(&node);
+    fn get_with_load_hint() {
+        let ring: HashRing<&str> = HashRing::new();
+        assert!(ring.get_with_load_hint(&"foo").is_err());
 
-            ring.add_node(node).unwrap();
+        let ring = ring_with_keys(vec![(1 << 62, "large"), ((1 << 62) + 1000, "small")]);
 
-            let range = ring.get_by_hash(&node).unwrap().range();
-            assert_eq!(range, KeyRange {
-                start: node_key,
-                end: node_key
-            });
+        for x in 0..1000 {
+            let (node, share) = ring.get_with_load_hint(&x).unwrap();
+
+            if node.data() == &"large" {
+                assert!(share > 0.99);
+            } else {
+                assert!(share < 0.01);
+            }
         }
 
-        // Multiple nodes.
-        {
-            let mut ring: HashRing<VNode> = HashRing::new();
-            let node1 = VNode::new("127.0.0.1", 1024, 1);
-            let node2 = VNode::new("127.0.0.1", 1024, 2);
-            let node_key1 = ring.key(&node1);
-            let node_key2 = ring.key(&node2);
+        let ring = ring_with_keys(vec![(0, "a"), (1 << 63, "b")]);
+        assert_eq!(ring.get_with_load_hint(&"foo").unwrap().1, 0.5);
 
-            ring.add_node(node1).unwrap();
-            ring.add_node(node2).unwrap();
+        // Colliding nodes after the first one own nothing, even when a key
+        // lands exactly on their position.
+        let key = ring.key(&"foo");
+        let ring = ring_with_keys(vec![
+            (key, "a"),
+            (key, "b"),
+            (key, "c"),
+            (key.wrapping_add(1 << 63), "d"),
+        ]);
 
-            let range = ring.get_by_hash(&node1).unwrap().range();
-            assert_eq!(range, KeyRange {
-                start: node_key1,
-                end: node_key2
-            });
+        let (node, share) = ring.get_with_load_hint(&"foo").unwrap();
+        assert_eq!(node.prev().key(), node.key());
+        assert_eq!(share, 0.0);
 
-            let range = ring.get_by_hash(&node2).unwrap().range();
-            assert_eq!(range, KeyRange {
-                start: node_key2,
-                end: node_key1
-            });
+        for x in 0..1000 {
+            let (node, share) = ring.get_with_load_hint(&x).unwrap();
+
+            let first = node.prev().key() != node.key();
+            assert_eq!(share, if first { 0.5 } else { 0.0 });
         }
+    }
 
-        // Invalid node.
-        {
-            let mut ring: HashRing<VNode> = HashRing::new();
-            let node1 = VNode::new("127.0.0.1", 1024, 1);
-            let node2 = VNode::new("127.0.0.1", 1024, 2);
+    #[test]
+    fn byte_nodes() {
+        let mut ring: HashRing<Vec<u8>> = HashRing::new();
+        assert!(ring.get_by_bytes(b"foo").is_err());
 
-            ring.add_node(node1).unwrap();
+        for node in [b"node-1", b"node-2", b"node-3"] {
+            ring.add_bytes(node).unwrap();
+        }
 
-            let result = ring.node(&node2);
-            assert!(matches!(result, Err(super::Error::NodeNotFound)));
+        assert_eq!(ring.add_bytes(b"node-1"), Err(Error::DuplicateNode));
+        assert!(ring.node(&b"node-2".to_vec()).is_ok());
+
+        for x in 0..100u32 {
+            let key = x.to_be_bytes();
+            assert_eq!(
+                ring.get_by_bytes(&key).unwrap().index,
+                ring.get_by_hash(&key.to_vec()).unwrap().index
+            );
         }
     }
 
     #[test]
-    fn iter() {
+    fn short_tag() {
+        let mut ring: HashRing<VNode> = HashRing::new();
         let node1 = VNode::new("127.0.0.1", 1024, 1);
         let node2 = VNode::new("127.0.0.1", 1024, 2);
-        let node3 = VNode::new("127.0.0.1", 1024, 3);
 
-        let mut ring: HashRing<VNode> = HashRing::new();
-        assert!(ring.iter(None).next().is_none());
+        ring.add_node(node1).unwrap();
+        ring.add_node(node2).unwrap();
 
-        ring.add_node(node1.clone()).unwrap();
-        assert_eq!(ring.iter(None).next().unwrap().data(), &node1);
+        let tag1 = ring.node(&node1).unwrap().short_tag();
+        let tag2 = ring.node(&node2).unwrap().short_tag();
+        assert_eq!(tag1.len(), 8);
+        assert_ne!(tag1, tag2);
 
-        ring.add_node(node2.clone()).unwrap();
-        ring.add_node(node3.clone()).unwrap();
+        for id in 3..=8 {
+            ring.add_node(VNode::new("127.0.0.1", 1024, id)).unwrap();
+        }
+        ring.remove_node(&node2).unwrap();
 
-        let mut iter = ring.iter(Some(ring.key(&node2)));
+        assert_eq!(ring.node(&node1).unwrap().short_tag(), tag1);
 
-        assert_eq!(iter.next().unwrap().data(), &node2);
-        assert_eq!(iter.next().unwrap().data(), &node3);
-        assert_eq!(iter.next().unwrap().data(), &node1);
-        assert!(iter.next().is_none());
+        let ring = ring_with_keys(vec![(0, "a"), (u64::MAX, "b")]);
+        assert_eq!(ring.get_by_index(0).unwrap().short_tag(), "aaaaaaaa");
+        assert_eq!(ring.get_by_index(1).unwrap().short_tag(), "77777777");
     }
 
+    #[cfg(feature = "std")]
     #[test]
-    fn add_node_unchecked() {
-        let node1 = VNode::new("127.0.0.1", 1024, 3);
+    fn load_distribution() {
+        let ring: HashRing<&str> = HashRing::new();
+        assert!(ring.load_distribution().is_empty());
+
+        let ring = ring_with_keys(vec![(10, "a"), (20, "b"), (30, "b"), (30, "c")]);
+        let distribution = ring.load_distribution();
+
+        assert_eq!(distribution.len(), 3);
+        assert_eq!(distribution[&"c"], 0.0);
+        assert!((distribution[&"b"] - 20.0 / u64::MAX as f64).abs() < 1e-12);
+        assert!((distribution.values().sum::<f64>() - 1.0).abs() < 1e-12);
+
+        let mut ring: HashRing<u32> = HashRing::new();
+        for node in 0..8 {
+            ring.add_weighted_node(node, 200).unwrap();
+        }
+
+        let distribution = ring.load_distribution();
+        assert_eq!(distribution.len(), 8);
+        assert!(distribution
+            .values()
+            .all(|&fraction| (fraction - 0.125).abs() < 0.03));
+    }
+
+    #[test]
+    fn simulate_removals() {
+        let node1 = VNode::new("127.0.0.1", 1024, 1);
         let node2 = VNode::new("127.0.0.1", 1024, 2);
-        let node3 = VNode::new("127.0.0.1", 1024, 1);
+        let node3 = VNode::new("127.0.0.1", 1024, 3);
+        let node4 = VNode::new("127.0.0.1", 1024, 4);
 
-        let mut ring1 = HashRing::new();
-        ring1.add_node(node1.clone()).unwrap();
-        ring1.add_node(node2.clone()).unwrap();
-        ring1.add_node(node3.clone()).unwrap();
+        let mut ring: HashRing<VNode> = HashRing::new();
+        ring.add_node(node1).unwrap();
+        ring.add_node(node2).unwrap();
+        ring.add_node(node3).unwrap();
 
-        let mut ring2 = HashRing::new();
-        ring2.add_node_unchecked(node1.clone());
-        ring2.add_node_unchecked(node2.clone());
-        ring2.add_node_unchecked(node3.clone());
-        ring2.sort();
+        // Removing each node on its own moves disjoint parts of the keyspace.
+        let total: f64 = [node1, node2, node3]
+            .iter()
+            .map(|node| ring.simulate_removals(&[*node])[0])
+            .sum();
+        assert!((total - 1.0).abs() < 1e-9);
 
-        assert_eq!(ring1.data, ring2.data);
+        let steps = ring.simulate_removals(&[node2, node4, node1, node3]);
+        assert_eq!(steps.len(), 4);
+        assert!(steps[0] > 0.0 && steps[0] < 1.0);
+        assert_eq!(steps[1], 0.0);
+        assert!(steps[2] > 0.0 && steps[2] < 1.0);
+        assert_eq!(steps[3], 1.0);
+
+        // The original ring is left untouched.
+        assert_eq!(ring.len(), 3);
     }
 }
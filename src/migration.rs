@@ -0,0 +1,276 @@
+#[cfg(feature = "std")]
+use {crate::range::merge_ranges, std::collections::HashMap};
+use {
+    crate::{
+        random_u64,
+        range::{KeyRange, RingKey},
+        Error,
+        HashRing,
+        NodeRef,
+        RingHasher,
+    },
+    alloc::vec::Vec,
+    core::hash::Hash,
+};
+
+/// Groups the moves of a migration plan by destination node. Each move is a
+/// `(range, source, destination)` tuple, where `source` and `destination` are
+/// node indices. Ranges moving to the same destination are merged, so each
+/// node gets a single, deoverlapped list of incoming ranges.
+#[cfg(feature = "std")]
+pub fn group_migration_by_dest<K>(
+    plan: &[(KeyRange<K>, usize, usize)],
+) -> HashMap<usize, Vec<KeyRange<K>>>
+where
+    K: PartialOrd + Ord + Clone,
+{
+    let mut groups: HashMap<usize, Vec<KeyRange<K>>> = HashMap::new();
+
+    for (range, _, dest) in plan {
+        groups.entry(*dest).or_default().push(range.clone());
+    }
+
+    groups
+        .into_iter()
+        .map(|(dest, ranges)| (dest, merge_ranges(ranges).collect()))
+        .collect()
+}
+
+/// Returns the owner of `key` in either the `old` or the `new` ring, or an
+/// error if the chosen ring is empty. About `new_fraction` of keys are routed
+/// using the `new` ring, which allows gradually ramping up traffic to a new
+/// topology. The choice is deterministic per key, and independent from the
+/// key's position on the rings.
+pub fn blended_get<'a, T, S, U>(
+    old: &'a HashRing<T, S>,
+    new: &'a HashRing<T, S>,
+    key: &U,
+    new_fraction: f64,
+) -> Result<NodeRef<'a, T, S>, Error>
+where
+    T: Hash,
+    S: RingHasher,
+    U: Hash,
+{
+    if (random_u64(key) as f64) < new_fraction * u64::MAX as f64 {
+        new.get_by_hash(key)
+    } else {
+        old.get_by_hash(key)
+    }
+}
+
+/// A range of keys along with its owner in the old and in the new ring. See
+/// `diff()`.
+pub type OwnerChange<'a, T, K> = (KeyRange<K>, Option<&'a T>, Option<&'a T>);
+
+/// Like `OwnerChange`, but owning the nodes, for plans outliving the old ring.
+/// See `HashRing::equalize()`.
+pub type OwnedChange<T, K> = (KeyRange<K>, Option<T>, Option<T>);
+
+/// Returns the ranges of keys changing owner between the `old` and the `new`
+/// ring, along with their owners in each, or `None` if that ring is empty.
+/// Adjacent ranges moving between the same pair of owners are merged. Like the
+/// ranges returned by `HashRing::locate()`, each range covers the keys after
+/// one boundary up to and including the next, since keys are routed to the
+/// first node at or after them.
+pub fn diff<'a, T, S>(
+    old: &'a HashRing<T, S>,
+    new: &'a HashRing<T, S>,
+) -> Vec<OwnerChange<'a, T, S::Key>>
+where
+    T: Hash + PartialEq,
+    S: RingHasher,
+    S::Key: RingKey,
+{
+    let mut boundaries = old.fingerprint();
+    boundaries.extend(new.fingerprint());
+    boundaries.sort();
+    boundaries.dedup();
+
+    // Keys between two consecutive boundaries have the same owner in both
+    // rings: the node at or after the second boundary.
+    let owner = |ring: &'a HashRing<T, S>, key: &S::Key| {
+        ring.get_by_key(key)
+            .ok()
+            .map(|node| &ring.data[node.index].data)
+    };
+
+    let mut moves: Vec<OwnerChange<'a, T, S::Key>> = Vec::new();
+
+    for (index, end) in boundaries.iter().enumerate() {
+        let start = &boundaries[(index + boundaries.len() - 1) % boundaries.len()];
+        let (from, to) = (owner(old, end), owner(new, end));
+
+        if from == to {
+            continue;
+        }
+
+        match moves.last_mut() {
+            Some((range, last_from, last_to))
+                if &range.end == start && *last_from == from && *last_to == to =>
+            {
+                range.end = *end;
+            }
+            _ => moves.push((KeyRange::new(*start, *end), from, to)),
+        }
+    }
+
+    // Merge the last and first ranges if they meet at the end of the ring.
+    if moves.len() > 1 {
+        let (first, last) = (&moves[0], &moves[moves.len() - 1]);
+
+        if last.0.end == first.0.start && last.1 == first.1 && last.2 == first.2 {
+            let last = moves.pop().unwrap();
+            moves[0].0.start = last.0.start;
+        }
+    }
+
+    // Shift the ranges by one, since ranges exclude their end while owners
+    // include the key at their boundary.
+    moves
+        .into_iter()
+        .map(|(range, from, to)| {
+            let range = KeyRange::new(range.start.wrapping_next(), range.end.wrapping_next());
+            (range, from, to)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::Node, std::ops::Range};
+
+    fn r(range: Range<u64>) -> KeyRange<u64> {
+        range.into()
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn group_by_dest() {
+        let plan = [
+            (r(10..20), 0, 1),
+            (r(40..50), 2, 0),
+            (r(20..30), 2, 1),
+            (r(60..70), 0, 1),
+            (r(45..55), 1, 0),
+        ];
+
+        let groups = group_migration_by_dest(&plan);
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[&0], vec![r(40..55)]);
+        assert_eq!(groups[&1], vec![r(10..30), r(60..70)]);
+
+        assert!(group_migration_by_dest::<u64>(&[]).is_empty());
+    }
+
+    #[test]
+    fn blended_routing() {
+        let mut old = HashRing::new();
+        let mut new = HashRing::new();
+
+        for node in 0..8 {
+            old.add_node(node).unwrap();
+            new.add_node(node + 100).unwrap();
+        }
+
+        let routed_new = (0..10_000)
+            .filter(|key| {
+                let node = *blended_get(&old, &new, key, 0.25).unwrap().data();
+                assert_eq!(*blended_get(&old, &new, key, 0.25).unwrap().data(), node);
+
+                node >= 100
+            })
+            .count();
+
+        assert!((routed_new as f64 / 10_000.0 - 0.25).abs() < 0.02);
+
+        for key in 0..100 {
+            assert_eq!(
+                blended_get(&old, &new, &key, 0.0).unwrap().data(),
+                old.get_by_hash(&key).unwrap().data()
+            );
+            assert_eq!(
+                blended_get(&old, &new, &key, 1.0).unwrap().data(),
+                new.get_by_hash(&key).unwrap().data()
+            );
+        }
+    }
+
+    #[test]
+    fn ring_diff() {
+        fn ring(nodes: &[(u64, &'static str)]) -> HashRing<&'static str> {
+            let mut ring = HashRing::new();
+            ring.data = nodes
+                .iter()
+                .map(|(key, data)| Node::new(*key, *data))
+                .collect();
+            ring
+        }
+
+        let old = ring(&[(10, "a"), (20, "b"), (30, "c")]);
+
+        assert!(diff(&old, &old).is_empty());
+
+        // Adding a node moves a single range.
+        let new = ring(&[(10, "a"), (20, "b"), (25, "d"), (30, "c")]);
+        assert_eq!(diff(&old, &new), vec![(r(21..26), Some(&"c"), Some(&"d"))]);
+
+        // Removing a node moves its range to the successor.
+        let new = ring(&[(10, "a"), (30, "c")]);
+        assert_eq!(diff(&old, &new), vec![(r(11..21), Some(&"b"), Some(&"c"))]);
+
+        // Wrapping ranges.
+        let new = ring(&[(5, "e"), (10, "a"), (20, "b"), (30, "c")]);
+        assert_eq!(diff(&old, &new), vec![(
+            KeyRange::new(31, 6),
+            Some(&"a"),
+            Some(&"e")
+        )]);
+
+        // Empty rings.
+        let empty = ring(&[]);
+        assert_eq!(diff(&empty, &old), vec![
+            (KeyRange::new(31, 11), None, Some(&"a")),
+            (r(11..21), None, Some(&"b")),
+            (r(21..31), None, Some(&"c")),
+        ]);
+
+        let new = ring(&[(15, "x")]);
+        assert_eq!(diff(&empty, &new), vec![(
+            KeyRange::new(16, 16),
+            None,
+            Some(&"x")
+        )]);
+    }
+
+    #[test]
+    fn diff_boundaries() {
+        let mut old: HashRing<u32> = HashRing::new();
+        let mut new: HashRing<u32> = HashRing::new();
+
+        for node in 0..8 {
+            old.add_node(node).unwrap();
+            new.add_node(node + (node % 2) * 100).unwrap();
+        }
+        new.add_node(1000).unwrap();
+
+        let moves = diff(&old, &new);
+        let owner = |ring: &HashRing<u32>, key: u64| *ring.get_by_key(&key).unwrap().data();
+
+        for boundary in old.fingerprint().into_iter().chain(new.fingerprint()) {
+            for key in [boundary.wrapping_sub(1), boundary, boundary.wrapping_add(1)] {
+                let (from, to) = (owner(&old, key), owner(&new, key));
+                let moved = moves.iter().find(|(range, ..)| range.contains(&key));
+
+                match moved {
+                    Some((_, Some(&moved_from), Some(&moved_to))) => {
+                        assert_eq!((moved_from, moved_to), (from, to));
+                    }
+                    Some(_) => unreachable!(),
+                    None => assert_eq!(from, to),
+                }
+            }
+        }
+    }
+}
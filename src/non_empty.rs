@@ -0,0 +1,103 @@
+use {
+    super::{DefaultHashBuilder, HashRing, NodeRef, RingHasher},
+    core::hash::Hash,
+};
+
+/// A `HashRing` that is known to contain at least one node, so lookups can't
+/// fail. See `HashRing::require_non_empty()`.
+#[derive(Clone)]
+pub struct NonEmptyHashRing<T, S: RingHasher = DefaultHashBuilder> {
+    ring: HashRing<T, S>,
+}
+
+impl<T, S> NonEmptyHashRing<T, S>
+where
+    T: Hash,
+    S: RingHasher,
+{
+    /// Returns the `NodeRef` for the node containing `key`.
+    #[inline]
+    pub fn get_by_hash<U: Hash>(&self, key: &U) -> NodeRef<'_, T, S> {
+        let key = self.ring.key(key);
+        let node = self.get_by_key(&key);
+        self.ring.observe(&key, node.index, 0);
+
+        node
+    }
+
+    /// Returns the `NodeRef` for the node containing `key`.
+    #[inline]
+    pub fn get_by_key(&self, key: &S::Key) -> NodeRef<'_, T, S> {
+        let index = match self.ring.find_node(key) {
+            Err(index) => index,
+            Ok(index) => index,
+        };
+
+        NodeRef {
+            ring: &self.ring,
+            index: if index == self.ring.len() { 0 } else { index },
+        }
+    }
+
+    /// Returns the underlying hash ring.
+    #[inline]
+    pub fn ring(&self) -> &HashRing<T, S> {
+        &self.ring
+    }
+
+    /// Unwraps the underlying hash ring.
+    #[inline]
+    pub fn into_inner(self) -> HashRing<T, S> {
+        self.ring
+    }
+}
+
+impl<T, S> HashRing<T, S>
+where
+    T: Hash,
+    S: RingHasher,
+{
+    /// Converts the ring into a `NonEmptyHashRing`, whose lookups skip the
+    /// empty check. Returns the ring back if it is empty.
+    pub fn require_non_empty(self) -> Result<NonEmptyHashRing<T, S>, HashRing<T, S>> {
+        if self.is_empty() {
+            Err(self)
+        } else {
+            Ok(NonEmptyHashRing { ring: self })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_empty_routing() {
+        let mut ring: HashRing<u32> = HashRing::new();
+        for node in 0..8 {
+            ring.add_node(node).unwrap();
+        }
+
+        let expected = (0..1000)
+            .map(|key| *ring.get_by_hash(&key).unwrap().data())
+            .collect::<Vec<_>>();
+
+        let Ok(ring) = ring.require_non_empty() else {
+            panic!("ring is not empty");
+        };
+        for (key, expected) in expected.into_iter().enumerate() {
+            assert_eq!(*ring.get_by_hash(&(key as i32)).data(), expected);
+        }
+
+        assert_eq!(ring.get_by_key(&u64::MAX).index, 0);
+        assert_eq!(ring.into_inner().len(), 8);
+    }
+
+    #[test]
+    fn empty_ring() {
+        let ring: HashRing<u32> = HashRing::new();
+        let ring = ring.require_non_empty().err().unwrap();
+        assert!(ring.is_empty());
+    }
+}
@@ -0,0 +1,285 @@
+use {
+    super::{Error, HashRing, NodeRef, RingHasher},
+    alloc::vec::Vec,
+    core::hash::Hash,
+};
+
+/// Write and read sets selected from a key's preference list. See
+/// `HashRing::quorum()`.
+pub struct QuorumSet<'a, T, S: RingHasher> {
+    nodes: Vec<NodeRef<'a, T, S>>,
+    write: usize,
+    read: usize,
+}
+
+impl<'a, T, S: RingHasher> QuorumSet<'a, T, S> {
+    /// Returns the full preference list, one node per distinct host.
+    #[inline]
+    pub fn nodes(&self) -> &[NodeRef<'a, T, S>] {
+        &self.nodes
+    }
+
+    /// Returns the nodes writes go to: the first `w` nodes of the preference
+    /// list.
+    #[inline]
+    pub fn write_set(&self) -> &[NodeRef<'a, T, S>] {
+        &self.nodes[..self.write]
+    }
+
+    /// Returns the nodes reads go to: the last `r` nodes of the preference
+    /// list. Since `w + r > n`, it always overlaps with the write set.
+    #[inline]
+    pub fn read_set(&self) -> &[NodeRef<'a, T, S>] {
+        &self.nodes[self.nodes.len() - self.read..]
+    }
+}
+
+impl<T, S> HashRing<T, S>
+where
+    T: Hash,
+    S: RingHasher,
+{
+    /// Selects a preference list of `n` nodes on distinct hosts for `key`,
+    /// along with write and read sets of `w` and `r` nodes. Returns `None`
+    /// if the sets would not be guaranteed to overlap (`w + r <= n`), if
+    /// `w` or `r` exceed `n`, or if the ring has fewer than `n` distinct
+    /// hosts.
+    pub fn quorum<U, K, F>(
+        &self,
+        key: &U,
+        n: usize,
+        w: usize,
+        r: usize,
+        host_of: F,
+    ) -> Option<QuorumSet<'_, T, S>>
+    where
+        U: Hash,
+        K: PartialEq,
+        F: Fn(&T) -> K,
+    {
+        if w + r <= n || w > n || r > n {
+            return None;
+        }
+
+        let nodes = self.distinct_nodes(key, n, |a, b| host_of(a) == host_of(b));
+
+        if nodes.len() < n {
+            return None;
+        }
+
+        Some(QuorumSet {
+            nodes,
+            write: w,
+            read: r,
+        })
+    }
+
+    /// Selects a preference list of `k` nodes on distinct hosts for `key`, or
+    /// returns an `Error::InsufficientReplicas` if the ring has fewer than `k`
+    /// distinct hosts.
+    pub fn require_replicas<U, K, F>(
+        &self,
+        key: &U,
+        k: usize,
+        host_of: F,
+    ) -> Result<Vec<NodeRef<'_, T, S>>, Error>
+    where
+        U: Hash,
+        K: PartialEq,
+        F: Fn(&T) -> K,
+    {
+        let nodes = self.distinct_nodes(key, k, |a, b| host_of(a) == host_of(b));
+
+        if nodes.len() < k {
+            return Err(Error::InsufficientReplicas {
+                available: nodes.len(),
+                required: k,
+            });
+        }
+
+        Ok(nodes)
+    }
+
+    /// Builds a preference list of `rf` nodes for `key`, placing replicas in
+    /// distinct zones where possible, like Cassandra's
+    /// `NetworkTopologyStrategy`. The ring is walked clockwise, skipping nodes
+    /// in zones that already have a replica. Once every zone has one, skipped
+    /// nodes are used first, in the order they were skipped, followed by the
+    /// remaining nodes. Returns fewer than `rf` nodes only if the ring has
+    /// fewer nodes.
+    pub fn preference_list_anti_affinity<U, Z, F>(
+        &self,
+        key: &U,
+        rf: usize,
+        zone_of: F,
+    ) -> Vec<NodeRef<'_, T, S>>
+    where
+        U: Hash,
+        Z: PartialEq,
+        F: Fn(&T) -> Z,
+    {
+        let mut zones = Vec::new();
+        for node in &self.data {
+            let zone = zone_of(&node.data);
+            if !zones.contains(&zone) {
+                zones.push(zone);
+            }
+        }
+
+        let mut used = Vec::new();
+        let mut skipped = Vec::new();
+        let mut nodes = Vec::new();
+
+        for node in self.iter(self.key(key)) {
+            if nodes.len() == rf {
+                break;
+            }
+
+            if used.len() == zones.len() {
+                // Every zone has a replica, so repeats are allowed.
+                nodes.extend(skipped.drain(..).take(rf - nodes.len()));
+
+                if nodes.len() < rf {
+                    nodes.push(node);
+                }
+
+                continue;
+            }
+
+            let zone = zone_of(node.data());
+            if used.contains(&zone) {
+                skipped.push(node);
+            } else {
+                used.push(zone);
+                nodes.push(node);
+            }
+        }
+
+        nodes.extend(skipped.into_iter().take(rf.saturating_sub(nodes.len())));
+
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ring() -> HashRing<(&'static str, u32)> {
+        let mut ring = HashRing::new();
+
+        for host in ["a", "b", "c", "d"] {
+            for id in 0..4 {
+                ring.add_node((host, id)).unwrap();
+            }
+        }
+
+        ring
+    }
+
+    #[test]
+    fn quorum_overlap() {
+        let ring = ring();
+        let host_of = |node: &(&'static str, u32)| node.0;
+
+        for key in 0..100 {
+            let quorum = ring.quorum(&key, 3, 2, 2, host_of).unwrap();
+
+            let mut hosts = quorum
+                .nodes()
+                .iter()
+                .map(|node| node.data().0)
+                .collect::<Vec<_>>();
+            hosts.sort();
+            hosts.dedup();
+            assert_eq!(hosts.len(), 3);
+
+            assert_eq!(quorum.write_set().len(), 2);
+            assert_eq!(quorum.read_set().len(), 2);
+            assert!(quorum
+                .write_set()
+                .iter()
+                .any(|w| quorum.read_set().iter().any(|r| w.data() == r.data())));
+        }
+    }
+
+    #[test]
+    fn quorum_invalid() {
+        let ring = ring();
+        let host_of = |node: &(&'static str, u32)| node.0;
+
+        // No guaranteed overlap.
+        assert!(ring.quorum(&"foo", 3, 1, 2, host_of).is_none());
+        assert!(ring.quorum(&"foo", 3, 0, 3, host_of).is_none());
+
+        // Not enough distinct hosts.
+        assert!(ring.quorum(&"foo", 5, 3, 3, host_of).is_none());
+
+        // Empty ring.
+        let ring: HashRing<(&'static str, u32)> = HashRing::new();
+        assert!(ring.quorum(&"foo", 1, 1, 1, host_of).is_none());
+    }
+
+    #[test]
+    fn require_replicas() {
+        let ring = ring();
+        let host_of = |node: &(&'static str, u32)| node.0;
+
+        let nodes = ring.require_replicas(&"foo", 3, host_of).unwrap();
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(nodes[0].index, ring.owner_index(&"foo").unwrap());
+
+        let mut ring: HashRing<(&'static str, u32)> = HashRing::new();
+        for host in ["a", "b"] {
+            for id in 0..4 {
+                ring.add_node((host, id)).unwrap();
+            }
+        }
+
+        assert_eq!(
+            ring.require_replicas(&"foo", 3, host_of).unwrap_err(),
+            Error::InsufficientReplicas {
+                available: 2,
+                required: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn anti_affinity() {
+        let ring = ring();
+        let zone_of = |node: &(&'static str, u32)| node.1 % 3;
+
+        for key in 0..100 {
+            let nodes = ring.preference_list_anti_affinity(&key, 3, zone_of);
+
+            let mut zones = nodes
+                .iter()
+                .map(|node| zone_of(node.data()))
+                .collect::<Vec<_>>();
+            zones.sort();
+            assert_eq!(zones, vec![0, 1, 2]);
+
+            // The first replica is always the owner.
+            assert_eq!(nodes[0].index, ring.owner_index(&key).unwrap());
+
+            // More replicas than zones repeat zones, but not nodes.
+            let nodes = ring.preference_list_anti_affinity(&key, 5, zone_of);
+            let mut indices = nodes.iter().map(|node| node.index).collect::<Vec<_>>();
+            indices.sort();
+            indices.dedup();
+            assert_eq!(indices.len(), 5);
+        }
+
+        assert_eq!(
+            ring.preference_list_anti_affinity(&"foo", 100, zone_of)
+                .len(),
+            ring.len()
+        );
+
+        let ring: HashRing<(&'static str, u32)> = HashRing::new();
+        assert!(ring
+            .preference_list_anti_affinity(&"foo", 3, zone_of)
+            .is_empty());
+    }
+}
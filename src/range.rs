@@ -1,6 +1,7 @@
 use {
-    serde::{Deserialize, Serialize},
-    std::{
+    alloc::vec::Vec,
+    core::{
+        cmp::Ordering,
         fmt::Debug,
         ops::{Range, RangeFrom, RangeTo},
     },
@@ -15,7 +16,8 @@ pub use merge::*;
 ///
 /// If `start >= end`, the range is considered wrapping and is equivalent to
 /// covering two ranges: `(..end)` and `(start..)`.
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KeyRange<Idx> {
     pub start: Idx,
     pub end: Idx,
@@ -49,6 +51,103 @@ impl<Idx: PartialOrd<Idx> + Clone> KeyRange<Idx> {
         self.contains(&other.start) || other.contains(&self.start)
     }
 
+    /// Returns the ranges of keys contained in both the range and `other`, or
+    /// an empty `Vec` if they don't overlap. The overlap of two ranges is not
+    /// always contiguous, e.g. `10..5` and `3..12` share both `3..5` and
+    /// `10..12`, so there are up to two ranges, ordered by `start`. The last
+    /// one may be wrapping.
+    pub fn intersection(&self, other: &Self) -> Vec<KeyRange<Idx>> {
+        if self.start == self.end {
+            return vec![other.clone()];
+        }
+
+        if other.start == other.end {
+            return vec![self.clone()];
+        }
+
+        // Intersect the non-wrapping pieces of both ranges, with `None` standing
+        // for the lowest and highest key respectively.
+        let mut pieces = Vec::new();
+
+        for (a_start, a_end) in self.pieces() {
+            for (b_start, b_end) in other.pieces() {
+                let start = match (a_start, b_start) {
+                    (None, bound) | (bound, None) => bound,
+                    (Some(a), Some(b)) => Some(if a < b { b } else { a }),
+                };
+
+                let end = match (a_end, b_end) {
+                    (None, bound) | (bound, None) => bound,
+                    (Some(a), Some(b)) => Some(if a < b { a } else { b }),
+                };
+
+                match (start, end) {
+                    (Some(start), Some(end)) if start.partial_cmp(end) != Some(Ordering::Less) => {}
+                    _ => pieces.push((start, end)),
+                }
+            }
+        }
+
+        let mut ranges = pieces
+            .iter()
+            .filter_map(|piece| match piece {
+                (Some(start), Some(end)) => Some(KeyRange::new((*start).clone(), (*end).clone())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        ranges.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(Ordering::Equal));
+
+        // Pieces touching the lowest and highest key only come from two
+        // wrapping ranges, and join into a single wrapping range.
+        let wrap_end = pieces.iter().find_map(|piece| match piece {
+            (None, end) => *end,
+            _ => None,
+        });
+
+        let wrap_start = pieces.iter().find_map(|piece| match piece {
+            (start, None) => *start,
+            _ => None,
+        });
+
+        if let (Some(start), Some(end)) = (wrap_start, wrap_end) {
+            ranges.push(KeyRange::new(start.clone(), end.clone()));
+        }
+
+        ranges
+    }
+
+    /// Splits the range at `at` into `start..at` and `at..end`, either of which
+    /// may be wrapping. Returns `None` if `at` is not contained in the range,
+    /// or is its `start`, since `start..start` would cover the full range
+    /// instead of being empty.
+    pub fn split_at(&self, at: &Idx) -> Option<(KeyRange<Idx>, KeyRange<Idx>)> {
+        if !self.contains(at) || at == &self.start {
+            return None;
+        }
+
+        Some((
+            KeyRange::new(self.start.clone(), at.clone()),
+            KeyRange::new(at.clone(), self.end.clone()),
+        ))
+    }
+
+    /// Returns the range covering the keys of both ranges, or `None` if they
+    /// neither overlap nor touch, in which case two ranges are needed. This is
+    /// a single step of `merge_ranges()`, so the result is a full range if
+    /// the ranges cover every key.
+    pub fn union(&self, other: &Self) -> Option<KeyRange<Idx>>
+    where
+        Idx: Ord,
+    {
+        let mut merged = merge_ranges(vec![self.clone(), other.clone()]);
+
+        match (merged.next(), merged.next()) {
+            (Some(range), None) => Some(range),
+            _ => None,
+        }
+    }
+
     /// Extends both `start` and `end` of the range to match `other`.
     pub fn extend(&mut self, other: &Self) {
         self.extend_start(other);
@@ -79,6 +178,16 @@ impl<Idx: PartialOrd<Idx> + Clone> KeyRange<Idx> {
         }
     }
 
+    /// Splits the range into its non-wrapping pieces, with `None` standing for
+    /// an unbounded side.
+    fn pieces(&self) -> Vec<(Option<&Idx>, Option<&Idx>)> {
+        if self.is_wrapping() {
+            vec![(None, Some(&self.end)), (Some(&self.start), None)]
+        } else {
+            vec![(Some(&self.start), Some(&self.end))]
+        }
+    }
+
     fn range_from(&self) -> RangeFrom<&Idx> {
         &self.start..
     }
@@ -97,12 +206,89 @@ impl<Idx> From<Range<Idx>> for KeyRange<Idx> {
     }
 }
 
-impl KeyRange<u64> {
-    pub fn size(&self) -> u64 {
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// An unsigned integer key, for which the size of a `KeyRange` can be
+/// computed. This trait is sealed, and implemented for all unsigned integer
+/// types.
+pub trait RingKey: sealed::Sealed + Copy + Ord {
+    /// The lowest key, `0`.
+    const MIN: Self;
+
+    /// The highest key.
+    const MAX: Self;
+
+    /// Subtracts `other` from the key, wrapping around at the boundary of the
+    /// type.
+    fn wrapping_sub(self, other: Self) -> Self;
+
+    /// Returns the key after this one, wrapping around to `0` after `MAX`.
+    fn wrapping_next(self) -> Self;
+
+    /// Divides the key by `n`, or returns `None` if `n` is zero.
+    fn checked_div_usize(self, n: usize) -> Option<Self>;
+
+    /// Converts the key to a float, losing precision for keys above `2^53`.
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_ring_key {
+    ($($ty:ty),*) => {
+        $(
+            impl sealed::Sealed for $ty {}
+
+            impl RingKey for $ty {
+                const MIN: Self = <$ty>::MIN;
+                const MAX: Self = <$ty>::MAX;
+
+                #[inline]
+                fn wrapping_sub(self, other: Self) -> Self {
+                    <$ty>::wrapping_sub(self, other)
+                }
+
+                #[inline]
+                fn wrapping_next(self) -> Self {
+                    self.wrapping_add(1)
+                }
+
+                #[inline]
+                fn checked_div_usize(self, n: usize) -> Option<Self> {
+                    // The quotient is at most `self`, so it fits the type.
+                    (self as u128).checked_div(n as u128).map(|quotient| quotient as $ty)
+                }
+
+                #[inline]
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_ring_key!(u8, u16, u32, u64, u128, usize);
+
+impl<Idx: RingKey> KeyRange<Idx> {
+    /// Returns the number of keys in the range. A full range, i.e. one where
+    /// `start == end`, has a size of `Idx::MAX`. Uses wrapping arithmetic, so
+    /// it never panics on overflow.
+    pub fn size(&self) -> Idx {
         if self.is_wrapping() {
-            u64::MAX - (self.start - self.end)
+            Idx::MAX.wrapping_sub(self.start.wrapping_sub(self.end))
         } else {
-            self.end - self.start
+            self.end.wrapping_sub(self.start)
+        }
+    }
+
+    /// Returns the size of the range when keys are treated linearly, or `None`
+    /// if the range is wrapping and thus has no linear size.
+    pub fn checked_size(&self) -> Option<Idx> {
+        if self.is_wrapping() {
+            None
+        } else {
+            Some(self.end.wrapping_sub(self.start))
         }
     }
 }
@@ -211,6 +397,146 @@ mod tests {
         }
     }
 
+    #[test]
+    fn intersection() {
+        fn assert_intersection(r1: KeyRange<u64>, r2: KeyRange<u64>, expected: &[KeyRange<u64>]) {
+            assert_eq!(r1.intersection(&r2), expected);
+            assert_eq!(r2.intersection(&r1), expected);
+        }
+
+        // =====
+        //    =====
+        assert_intersection(KeyRange::new(5, 10), KeyRange::new(8, 13), &[
+            KeyRange::new(8, 10),
+        ]);
+
+        // =====
+        //      =====
+        assert_intersection(KeyRange::new(5, 10), KeyRange::new(10, 15), &[]);
+
+        //     =====
+        // ====     ====
+        assert_intersection(KeyRange::new(5, 10), KeyRange::new(10, 5), &[]);
+
+        //     =====
+        // ======   ====
+        assert_intersection(KeyRange::new(5, 10), KeyRange::new(10, 7), &[
+            KeyRange::new(5, 7),
+        ]);
+
+        //       =====
+        // ======   ====
+        assert_intersection(KeyRange::new(5, 10), KeyRange::new(7, 5), &[KeyRange::new(
+            7, 10,
+        )]);
+
+        //       =====
+        // =============
+        assert_intersection(KeyRange::new(5, 10), KeyRange::new(5, 5), &[KeyRange::new(
+            5, 10,
+        )]);
+
+        // =====     ====
+        // =======  =====
+        assert_intersection(KeyRange::new(10, 5), KeyRange::new(9, 6), &[KeyRange::new(
+            10, 5,
+        )]);
+
+        // Non-contiguous overlaps.
+        //   ====
+        // ===   =====
+        assert_intersection(KeyRange::new(10, 5), KeyRange::new(3, 12), &[
+            KeyRange::new(3, 5),
+            KeyRange::new(10, 12),
+        ]);
+
+        // ===   ====
+        // ==  ======
+        assert_intersection(KeyRange::new(10, 8), KeyRange::new(7, 3), &[
+            KeyRange::new(7, 8),
+            KeyRange::new(10, 3),
+        ]);
+
+        // Full ranges.
+        assert_eq!(KeyRange::new(5, 5).intersection(&KeyRange::new(7, 7)), [
+            KeyRange::new(7, 7)
+        ]);
+    }
+
+    #[test]
+    fn split() {
+        let range = KeyRange::new(5, 10);
+        assert_eq!(
+            range.split_at(&7),
+            Some((KeyRange::new(5, 7), KeyRange::new(7, 10)))
+        );
+        assert_eq!(range.split_at(&5), None);
+        assert_eq!(range.split_at(&10), None);
+        assert_eq!(range.split_at(&12), None);
+
+        // Wrapping ranges split into a wrapping and a non-wrapping piece.
+        let range = KeyRange::new(10, 5);
+        assert_eq!(
+            range.split_at(&2),
+            Some((KeyRange::new(10, 2), KeyRange::new(2, 5)))
+        );
+        assert_eq!(
+            range.split_at(&12),
+            Some((KeyRange::new(10, 12), KeyRange::new(12, 5)))
+        );
+        assert_eq!(range.split_at(&7), None);
+
+        // Full ranges.
+        assert_eq!(
+            KeyRange::new(5, 5).split_at(&2),
+            Some((KeyRange::new(5, 2), KeyRange::new(2, 5)))
+        );
+        assert_eq!(KeyRange::new(5, 5).split_at(&5), None);
+    }
+
+    #[test]
+    fn union() {
+        // Regular ranges.
+        let range = KeyRange::new(5u64, 10);
+        assert_eq!(
+            range.union(&KeyRange::new(8, 12)),
+            Some(KeyRange::new(5, 12))
+        );
+        assert_eq!(
+            range.union(&KeyRange::new(10, 12)),
+            Some(KeyRange::new(5, 12))
+        );
+        assert_eq!(range.union(&KeyRange::new(6, 7)), Some(range.clone()));
+        assert_eq!(range.union(&KeyRange::new(11, 12)), None);
+
+        // Wrapping ranges.
+        let range = KeyRange::new(10u64, 5);
+        assert_eq!(
+            range.union(&KeyRange::new(3, 7)),
+            Some(KeyRange::new(10, 7))
+        );
+        assert_eq!(
+            range.union(&KeyRange::new(8, 10)),
+            Some(KeyRange::new(8, 5))
+        );
+        assert_eq!(range.union(&KeyRange::new(12, 3)), Some(range.clone()));
+        assert_eq!(range.union(&KeyRange::new(6, 8)), None);
+
+        // Ranges covering every key yield a full range.
+        assert_eq!(
+            range.union(&KeyRange::new(5, 10)),
+            Some(KeyRange::new(5, 5))
+        );
+        assert_eq!(
+            KeyRange::new(0u64, 10).union(&KeyRange::new(10, 0)),
+            Some(KeyRange::new(0, 0))
+        );
+        assert_eq!(
+            KeyRange::new(5u64, 5).union(&KeyRange::new(1, 2)),
+            Some(KeyRange::new(5, 5))
+        );
+    }
+
     #[test]
     fn extension() {
         {
@@ -332,6 +658,45 @@ mod tests {
         assert_eq!(KeyRange::new(10, 9).size(), u64::MAX - 1);
 
         // Regular ranges.
-        assert_eq!(KeyRange::new(5, 10).size(), 5);
+        assert_eq!(KeyRange::new(5u64, 10).size(), 5);
+
+        // Other key widths.
+        assert_eq!(KeyRange::new(10u32, 9).size(), u32::MAX - 1);
+        assert_eq!(KeyRange::new(5u32, 10).size(), 5);
+        assert_eq!(KeyRange::new(10u128, 10).size(), u128::MAX);
+        assert_eq!(KeyRange::new(u128::MAX, 0).size(), 0);
+        assert_eq!(KeyRange::new(5u128, 10).checked_size(), Some(5));
+    }
+
+    // Only meaningful with overflow checks enabled.
+    #[cfg(debug_assertions)]
+    #[test]
+    fn size_no_overflow() {
+        // Wrapping ranges.
+        assert_eq!(KeyRange::new(u64::MAX, 0).size(), 0);
+        assert_eq!(KeyRange::new(u64::MAX, u64::MAX).size(), u64::MAX);
+        assert_eq!(KeyRange::new(1, 0).size(), u64::MAX - 1);
+
+        // Regular ranges.
+        assert_eq!(KeyRange::new(0, u64::MAX).size(), u64::MAX);
+        assert_eq!(KeyRange::new(u64::MAX - 1, u64::MAX).size(), 1);
+
+        for start in [0, 1, u64::MAX / 2, u64::MAX - 1, u64::MAX] {
+            for end in [0, 1, u64::MAX / 2, u64::MAX - 1, u64::MAX] {
+                KeyRange::new(start, end).size();
+            }
+        }
+    }
+
+    #[test]
+    fn checked_size() {
+        // Wrapping ranges.
+        assert_eq!(KeyRange::new(0u64, 0).checked_size(), None);
+        assert_eq!(KeyRange::new(10u64, 9).checked_size(), None);
+        assert_eq!(KeyRange::new(u64::MAX, 0).checked_size(), None);
+
+        // Regular ranges.
+        assert_eq!(KeyRange::new(5u64, 10).checked_size(), Some(5));
+        assert_eq!(KeyRange::new(0, u64::MAX).checked_size(), Some(u64::MAX));
     }
 }
@@ -1,4 +1,7 @@
-use super::KeyRange;
+use {
+    super::{KeyRange, RingKey},
+    alloc::vec::Vec,
+};
 
 pub struct MergedRanges<K, I: IntoIterator<Item = KeyRange<K>>> {
     values: I,
@@ -6,7 +9,8 @@ pub struct MergedRanges<K, I: IntoIterator<Item = KeyRange<K>>> {
 }
 
 /// Sorts the provided ranges and returns an iterator that yields merged
-/// (deoverlapped) ranges.
+/// (deoverlapped) ranges. A wrapping range, if any, is yielded last, and
+/// ranges covering every key collapse into a single full range.
 pub fn merge_ranges<K, I>(
     ranges: I,
 ) -> MergedRanges<K, <Vec<KeyRange<K>> as IntoIterator>::IntoIter>
@@ -17,7 +21,39 @@ where
     let mut ranges = ranges.into();
     ranges.sort_by(|a, b| a.start.cmp(&b.start));
 
-    merge_ranges_sorted(ranges)
+    merge_ranges_sorted(canonicalize(merge_ranges_sorted(ranges).collect()))
+}
+
+/// Internal function merging the wrapping range yielded last by
+/// `merge_ranges_sorted` with the ranges at the start of the keyspace, which
+/// it overlaps or touches, collapsing it into a full range if it then meets
+/// itself.
+fn canonicalize<K>(mut merged: Vec<KeyRange<K>>) -> Vec<KeyRange<K>>
+where
+    K: PartialOrd + Ord + Clone,
+{
+    let mut wrapping = match merged.last() {
+        Some(range) if range.is_wrapping() => merged.pop().unwrap(),
+        _ => return merged,
+    };
+
+    let absorbed = merged
+        .iter()
+        .take_while(|range| range.start <= wrapping.end)
+        .count();
+
+    for range in merged.drain(..absorbed) {
+        if range.end > wrapping.end {
+            wrapping.end = range.end;
+        }
+    }
+
+    if wrapping.end >= wrapping.start {
+        return vec![KeyRange::new(wrapping.start.clone(), wrapping.start)];
+    }
+
+    merged.push(wrapping);
+    merged
 }
 
 /// Returns an iterator that yields merged (deoverlapped) ranges from the input.
@@ -36,6 +72,80 @@ where
     }
 }
 
+/// Returns an iterator that yields merged (deoverlapped) ranges from the input,
+/// like `merge_ranges()`. The input iterator must yield ranges sorted on by
+/// `start` descending. The input is collected and reversed, which avoids
+/// sorting it, but unlike `merge_ranges_sorted()` this is not a streaming
+/// merge.
+pub fn merge_ranges_sorted_desc<K, T>(
+    ranges: T,
+) -> MergedRanges<K, <Vec<KeyRange<K>> as IntoIterator>::IntoIter>
+where
+    K: PartialOrd + Ord + Clone,
+    T: IntoIterator<Item = KeyRange<K>>,
+{
+    let mut ranges = ranges.into_iter().collect::<Vec<_>>();
+    ranges.reverse();
+
+    merge_ranges_sorted(canonicalize(merge_ranges_sorted(ranges).collect()))
+}
+
+/// Returns `true` if the provided ranges, once merged, cover every key, i.e.
+/// if there are no keys outside of all the ranges.
+pub fn covers_full_ring<K, I>(ranges: I) -> bool
+where
+    K: PartialOrd + Ord + Clone,
+    I: IntoIterator<Item = KeyRange<K>>,
+{
+    // Full coverage always merges into a single range with `start == end`.
+    let mut merged = merge_ranges(ranges.into_iter().collect::<Vec<_>>());
+
+    matches!(merged.next(), Some(range) if range.start == range.end)
+}
+
+/// Returns an iterator that yields the ranges of keys not covered by any of
+/// the provided ranges, sorted by `start`, with the last one possibly wrapping.
+/// Yields nothing if the ranges cover every key, and a single full range if
+/// there are no ranges.
+pub fn gaps<K, I>(ranges: I) -> impl Iterator<Item = KeyRange<K>>
+where
+    K: RingKey,
+    I: IntoIterator<Item = KeyRange<K>>,
+{
+    let mut merged = merge_ranges(ranges.into_iter().collect::<Vec<_>>()).collect::<Vec<_>>();
+
+    if merged.is_empty() {
+        return vec![KeyRange::new(K::MAX, K::MAX)].into_iter();
+    }
+
+    let wrapping = match merged.last() {
+        Some(range) if range.start == range.end => return Vec::new().into_iter(),
+        Some(range) if range.is_wrapping() => merged.pop(),
+        _ => None,
+    };
+
+    let mut gaps = Vec::new();
+
+    if let (Some(wrapping), Some(first)) = (&wrapping, merged.first()) {
+        gaps.push(KeyRange::new(wrapping.end, first.start));
+    }
+
+    gaps.extend(
+        merged
+            .windows(2)
+            .map(|pair| KeyRange::new(pair[0].end, pair[1].start)),
+    );
+
+    match (&wrapping, merged.first(), merged.last()) {
+        (Some(wrapping), _, Some(last)) => gaps.push(KeyRange::new(last.end, wrapping.start)),
+        (Some(wrapping), _, None) => gaps.push(KeyRange::new(wrapping.end, wrapping.start)),
+        (None, Some(first), Some(last)) => gaps.push(KeyRange::new(last.end, first.start)),
+        (None, ..) => {}
+    }
+
+    gaps.into_iter()
+}
+
 impl<K, I> Iterator for MergedRanges<K, I>
 where
     K: PartialOrd + Ord + Clone,
@@ -117,4 +227,124 @@ mod tests {
 
         assert_eq!(merged, vec![r(10..5)]);
     }
+
+    #[test]
+    fn merge_full_coverage() {
+        assert_eq!(merge_ranges(Vec::<KeyRange<u64>>::new()).count(), 0);
+        assert_eq!(merge_ranges([r(1..2)]).collect::<Vec<_>>(), vec![r(1..2)]);
+        assert_eq!(
+            merge_ranges([KeyRange::new(5, 5)]).collect::<Vec<_>>(),
+            vec![KeyRange::new(5, 5)]
+        );
+
+        // Ranges covering every key collapse into a single full range.
+        let ranges = [r(0..10), KeyRange::new(10, 0)];
+        assert_eq!(merge_ranges(ranges).collect::<Vec<_>>(), vec![
+            KeyRange::new(0, 0)
+        ]);
+
+        let ranges = [KeyRange::new(10, 3), r(3..10)];
+        assert_eq!(merge_ranges(ranges).collect::<Vec<_>>(), vec![
+            KeyRange::new(3, 3)
+        ]);
+
+        let ranges = [r(1..2), r(5..7), KeyRange::new(7, 5)];
+        assert_eq!(merge_ranges(ranges).collect::<Vec<_>>(), vec![
+            KeyRange::new(5, 5)
+        ]);
+
+        // Ranges at the start of the keyspace merge with the wrapping range.
+        let ranges = [r(3..5), KeyRange::new(10, 3), r(1..2)];
+        assert_eq!(merge_ranges(ranges).collect::<Vec<_>>(), vec![
+            KeyRange::new(10, 5)
+        ]);
+
+        let ranges = [r(4..6), KeyRange::new(10, 3), r(1..2)];
+        assert_eq!(merge_ranges(ranges).collect::<Vec<_>>(), vec![
+            r(4..6),
+            KeyRange::new(10, 3)
+        ]);
+    }
+
+    #[test]
+    fn merge_descending_ranges() {
+        let ranges = [r(8..10), r(3..6), r(2..5), r(1..4)];
+        let merged = merge_ranges_sorted_desc(ranges.clone()).collect::<Vec<_>>();
+
+        assert_eq!(merged, vec![r(1..6), r(8..10)]);
+        assert_eq!(merged, merge_ranges(ranges).collect::<Vec<_>>());
+
+        let ranges = [r(25..30), KeyRange::new(11, 4), KeyRange::new(10, 5)];
+        let merged = merge_ranges_sorted_desc(ranges.clone()).collect::<Vec<_>>();
+
+        assert_eq!(merged, merge_ranges(ranges).collect::<Vec<_>>());
+        // Ranges covering every key collapse into a single full range.
+        let ranges = [KeyRange::new(10, 0), r(5..10), r(0..5)];
+        let merged = merge_ranges_sorted_desc(ranges.clone()).collect::<Vec<_>>();
+
+        assert_eq!(merged, vec![KeyRange::new(0, 0)]);
+        assert_eq!(merged, merge_ranges(ranges).collect::<Vec<_>>());
+
+        let ranges = [KeyRange::new(7, 5), r(5..7), r(1..2)];
+        let merged = merge_ranges_sorted_desc(ranges.clone()).collect::<Vec<_>>();
+
+        assert_eq!(merged, vec![KeyRange::new(5, 5)]);
+        assert_eq!(merged, merge_ranges(ranges).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn full_coverage() {
+        assert!(!covers_full_ring(Vec::<KeyRange<u64>>::new()));
+
+        // Single ranges.
+        assert!(covers_full_ring([KeyRange::new(5, 5)]));
+        assert!(!covers_full_ring([KeyRange::new(10, 5)]));
+        assert!(!covers_full_ring([r(0..u64::MAX)]));
+
+        // Adjacent ranges.
+        assert!(covers_full_ring([r(0..5), r(5..10), KeyRange::new(10, 0)]));
+        assert!(covers_full_ring([r(5..7), KeyRange::new(7, 5), r(1..2)]));
+        assert!(!covers_full_ring([r(0..5), r(6..10), KeyRange::new(10, 0)]));
+
+        // Overlapping ranges.
+        assert!(covers_full_ring([KeyRange::new(10, 3), r(2..12)]));
+        assert!(covers_full_ring([
+            KeyRange::new(10, 2),
+            KeyRange::new(12, 8),
+            r(0..11)
+        ]));
+        assert!(!covers_full_ring([KeyRange::new(3, 1), r(2..3)]));
+    }
+
+    #[test]
+    fn find_gaps() {
+        let gaps_of = |ranges: Vec<KeyRange<u64>>| gaps(ranges).collect::<Vec<_>>();
+
+        assert_eq!(gaps_of(vec![]), vec![KeyRange::new(u64::MAX, u64::MAX)]);
+        assert_eq!(gaps_of(vec![KeyRange::new(5, 5)]), vec![]);
+        assert_eq!(
+            gaps_of(vec![r(0..5), r(5..10), KeyRange::new(10, 0)]),
+            vec![]
+        );
+
+        // Regular ranges.
+        assert_eq!(gaps_of(vec![r(5..10)]), vec![KeyRange::new(10, 5)]);
+        assert_eq!(gaps_of(vec![r(5..8), r(1..3)]), vec![
+            r(3..5),
+            KeyRange::new(8, 1)
+        ]);
+
+        // Wrapping ranges.
+        assert_eq!(gaps_of(vec![KeyRange::new(10, 5)]), vec![r(5..10)]);
+        assert_eq!(gaps_of(vec![r(1..2), KeyRange::new(10, 5)]), vec![r(5..10)]);
+        assert_eq!(
+            gaps_of(vec![r(0..5), r(6..10), KeyRange::new(10, 0)]),
+            vec![r(5..6)]
+        );
+        assert_eq!(gaps_of(vec![KeyRange::new(10, 3), r(2..4), r(6..7)]), vec![
+            r(4..6),
+            r(7..10)
+        ]);
+        assert_eq!(gaps_of(vec![KeyRange::new(10, 3), r(2..12)]), vec![]);
+    }
 }
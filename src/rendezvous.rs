@@ -0,0 +1,163 @@
+use {
+    super::{DefaultHashBuilder, Error, RingHasher},
+    alloc::vec::Vec,
+    core::{cmp::Reverse, hash::Hash},
+};
+
+/// Rendezvous (highest random weight) hashing, an alternative to `HashRing`
+/// for a small number of nodes. Each key goes to the node with the highest
+/// score, hashing the node along with the key. Keys are evenly balanced
+/// without virtual nodes, and membership changes only move the keys of the
+/// added or removed node, at the cost of lookups taking linear time.
+#[derive(Clone)]
+pub struct RendezvousRing<T, S = DefaultHashBuilder> {
+    hash_builder: S,
+    nodes: Vec<T>,
+}
+
+impl<T> RendezvousRing<T> {
+    /// Create a new `RendezvousRing`.
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<T> Default for RendezvousRing<T> {
+    fn default() -> Self {
+        RendezvousRing {
+            hash_builder: DefaultHashBuilder,
+            nodes: Vec::new(),
+        }
+    }
+}
+
+impl<T, S> RendezvousRing<T, S>
+where
+    T: Hash + PartialEq,
+    S: RingHasher,
+{
+    /// Creates an empty `RendezvousRing` which will use the given hash builder.
+    pub fn with_hasher(hash_builder: S) -> Self {
+        RendezvousRing {
+            hash_builder,
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Get the number of nodes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns true if there are no nodes.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Adds `node`. Returns the new node's index, or an error if the node was
+    /// already added.
+    pub fn add_node(&mut self, node: T) -> Result<usize, Error> {
+        if self.nodes.contains(&node) {
+            return Err(Error::DuplicateNode);
+        }
+
+        self.nodes.push(node);
+
+        Ok(self.nodes.len() - 1)
+    }
+
+    /// Removes `node`. Returns an `Error` if the node was not added. The other
+    /// nodes keep their insertion order, so the indices of the nodes added
+    /// after it shift down by one, as with `Vec::remove()`.
+    pub fn remove_node(&mut self, node: &T) -> Result<(), Error> {
+        let index = self
+            .nodes
+            .iter()
+            .position(|other| other == node)
+            .ok_or(Error::NodeNotFound)?;
+
+        self.nodes.remove(index);
+
+        Ok(())
+    }
+
+    /// Returns the node with the highest score for `key`, or an error if there
+    /// are no nodes.
+    pub fn get<U: Hash>(&self, key: &U) -> Result<&T, Error> {
+        self.nodes
+            .iter()
+            .max_by_key(|node| self.score(node, key))
+            .ok_or(Error::NodeNotFound)
+    }
+
+    /// Returns up to `n` nodes for `key`, in descending order of score, or an
+    /// error if there are no nodes. If there are fewer than `n` nodes, all of
+    /// them are returned.
+    pub fn get_n<U: Hash>(&self, key: &U, n: usize) -> Result<Vec<&T>, Error> {
+        if self.nodes.is_empty() {
+            return Err(Error::NodeNotFound);
+        }
+
+        let mut nodes = self
+            .nodes
+            .iter()
+            .map(|node| (self.score(node, key), node))
+            .collect::<Vec<_>>();
+        nodes.sort_by_key(|(score, _)| Reverse(score.clone()));
+
+        Ok(nodes.into_iter().take(n).map(|(_, node)| node).collect())
+    }
+
+    fn score<U: Hash>(&self, node: &T, key: &U) -> S::Key {
+        self.hash_builder.get_key((node, key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendezvous() {
+        let mut ring = RendezvousRing::new();
+        assert_eq!(ring.get(&"foo"), Err(Error::NodeNotFound));
+        assert_eq!(ring.get_n(&"foo", 2), Err(Error::NodeNotFound));
+
+        for node in ["a", "b", "c", "d"] {
+            ring.add_node(node).unwrap();
+        }
+        assert_eq!(ring.add_node("a"), Err(Error::DuplicateNode));
+        assert_eq!(ring.len(), 4);
+
+        let mut counts = [0; 4];
+        for key in 0..4000 {
+            let node = *ring.get(&key).unwrap();
+            counts[(node.as_bytes()[0] - b'a') as usize] += 1;
+
+            let nodes = ring.get_n(&key, 3).unwrap();
+            assert_eq!(nodes.len(), 3);
+            assert_eq!(*nodes[0], node);
+        }
+        assert!(counts.iter().all(|&count| (900..1100).contains(&count)));
+
+        // Removing a node only moves its own keys.
+        let before = (0..4000)
+            .map(|key| *ring.get(&key).unwrap())
+            .collect::<Vec<_>>();
+        ring.remove_node(&"b").unwrap();
+        assert_eq!(ring.remove_node(&"b"), Err(Error::NodeNotFound));
+
+        for (key, before) in (0..4000).zip(before) {
+            let after = *ring.get(&key).unwrap();
+            assert!(after == before || before == "b");
+        }
+
+        assert_eq!(ring.get_n(&"foo", 10).unwrap().len(), 3);
+
+        // Indices follow insertion order, without gaps.
+        assert_eq!(ring.nodes, vec!["a", "c", "d"]);
+        assert_eq!(ring.add_node("e"), Ok(3));
+    }
+}
@@ -0,0 +1,430 @@
+#[cfg(feature = "std")]
+use {super::NodeRef, std::collections::HashMap};
+use {
+    super::{Error, HashRing, RingHasher},
+    alloc::collections::VecDeque,
+    core::{
+        cell::Cell,
+        hash::{BuildHasher, Hash, Hasher},
+        marker::PhantomData,
+    },
+};
+
+/// Router with its own prebuilt hasher. See `HashRing::thread_local_router()`.
+pub struct TlRouter<'a, T, S: BuildHasher + Clone> {
+    ring: &'a HashRing<T, S>,
+    hasher: S::Hasher,
+    // Routers are meant to stay on the thread that created them.
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+impl<'a, T, S> TlRouter<'a, T, S>
+where
+    T: Hash,
+    S: BuildHasher + Clone,
+    S::Hasher: Clone,
+{
+    /// Returns the index of the node containing `key`, or an error if the hash
+    /// ring is empty. Keys are hashed with a copy of the prebuilt hasher, which
+    /// yields the same keys as the ring's hash builder.
+    #[inline]
+    pub fn route<U: Hash>(&self, key: &U) -> Result<usize, Error> {
+        let mut hasher = self.hasher.clone();
+        key.hash(&mut hasher);
+
+        let key = hasher.finish();
+        let index = self.ring.get_by_key(&key)?.index;
+        self.ring.observe(&key, index, 0);
+
+        Ok(index)
+    }
+}
+
+/// Router caching the owners of the most recently routed keys, for rings with
+/// hot keys. See `HashRing::caching_router()`.
+///
+/// The router doesn't borrow the ring, which may change between lookups. The
+/// cache is dropped whenever the ring's `version()` changes, or when the router
+/// is used with a different ring than the last one, including clones of it.
+pub struct CachingRouter<S: RingHasher> {
+    // Least recently used entries first.
+    entries: VecDeque<(S::Key, usize)>,
+    capacity: usize,
+    ring: usize,
+    version: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl<S: RingHasher> CachingRouter<S> {
+    /// Returns the index of the node containing `key` in `ring`, or an error if
+    /// the hash ring is empty.
+    pub fn route<T, U>(&mut self, ring: &HashRing<T, S>, key: &U) -> Result<usize, Error>
+    where
+        T: Hash,
+        U: Hash,
+    {
+        if self.ring != ring.id || self.version != ring.version() {
+            self.entries.clear();
+            self.ring = ring.id;
+            self.version = ring.version();
+        }
+
+        let key = ring.key(key);
+
+        if let Some(pos) = self.entries.iter().position(|(cached, _)| *cached == key) {
+            // Safe unwrap, since the position was just found.
+            let entry = self.entries.remove(pos).unwrap();
+            let index = entry.1;
+            self.entries.push_back(entry);
+            self.hits += 1;
+            ring.observe(&key, index, 0);
+
+            return Ok(index);
+        }
+
+        self.misses += 1;
+
+        let index = ring.get_by_key(&key)?.index;
+        ring.observe(&key, index, 0);
+
+        if self.capacity > 0 {
+            if self.entries.len() == self.capacity {
+                self.entries.pop_front();
+            }
+
+            self.entries.push_back((key, index));
+        }
+
+        Ok(index)
+    }
+
+    /// Returns the number of lookups served from the cache.
+    #[inline]
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns the number of lookups that missed the cache.
+    #[inline]
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+}
+
+/// Router implementing consistent hashing with bounded loads, which tracks the
+/// load of each node. See `HashRing::balanced_router()`.
+#[cfg(feature = "std")]
+pub struct BalancedRouter<'a, T, S: RingHasher> {
+    ring: &'a HashRing<T, S>,
+    loads: HashMap<&'a T, u64>,
+    total: u64,
+    nodes: usize,
+    capacity_factor: f64,
+}
+
+#[cfg(feature = "std")]
+impl<'a, T, S> BalancedRouter<'a, T, S>
+where
+    T: Hash + Eq,
+    S: RingHasher,
+{
+    /// Returns the `NodeRef` for the first node clockwise from `key` whose load
+    /// is below its capacity, and increments the node's load, or returns an
+    /// error if the hash ring is empty. The capacity is `capacity_factor` times
+    /// the average load, including this request, rounded up.
+    pub fn route<U: Hash>(&mut self, key: &U) -> Result<NodeRef<'a, T, S>, Error> {
+        if self.nodes == 0 {
+            return Err(Error::NodeNotFound);
+        }
+
+        let capacity =
+            ((self.total + 1) as f64 / self.nodes as f64 * self.capacity_factor).ceil() as u64;
+
+        let ring = self.ring;
+        let key = ring.key(key);
+        let (hops, node) = ring
+            .iter(key.clone())
+            .enumerate()
+            .find(|(_, node)| self.load(node.data()) < capacity)
+            .ok_or(Error::NodeNotFound)?;
+        ring.observe(&key, node.index, hops);
+
+        *self.loads.entry(&ring.data[node.index].data).or_insert(0) += 1;
+        self.total += 1;
+
+        Ok(node)
+    }
+
+    /// Decrements the load of `node`, e.g. once a request routed to it is done.
+    /// Nodes without load are left unchanged.
+    pub fn release(&mut self, node: &T) {
+        if let Some(load) = self.loads.get_mut(node).filter(|load| **load > 0) {
+            *load -= 1;
+            self.total -= 1;
+        }
+    }
+
+    /// Returns the current load of `node`.
+    #[inline]
+    pub fn load(&self, node: &T) -> u64 {
+        self.loads.get(node).copied().unwrap_or(0)
+    }
+
+    /// Returns the current loads of all nodes that were routed to.
+    #[inline]
+    pub fn loads(&self) -> &HashMap<&'a T, u64> {
+        &self.loads
+    }
+}
+
+impl<T, S> HashRing<T, S>
+where
+    T: Hash,
+    S: BuildHasher + Clone,
+    S::Hasher: Clone,
+{
+    /// Returns a router for use on the current thread. The router builds a
+    /// hasher once and clones it for every key instead of building a new one
+    /// per lookup, so hashers with expensive setup are only set up once per
+    /// thread. For the default hash builder, this is equivalent to
+    /// `owner_index()`.
+    pub fn thread_local_router(&self) -> TlRouter<'_, T, S> {
+        TlRouter {
+            ring: self,
+            hasher: self.hash_builder.build_hasher(),
+            _not_sync: PhantomData,
+        }
+    }
+}
+
+impl<T, S> HashRing<T, S>
+where
+    T: Hash,
+    S: RingHasher,
+{
+    /// Returns a router implementing consistent hashing with bounded loads,
+    /// where each key goes to the first node clockwise from it whose load is
+    /// below `capacity_factor` times the average load. The number of distinct
+    /// nodes is counted once here, so the router must not outlive changes to
+    /// the ring, which the borrow enforces. A `capacity_factor` below `1.0` is
+    /// treated as `1.0`, so there is always a node below capacity.
+    #[cfg(feature = "std")]
+    pub fn balanced_router(&self, capacity_factor: f64) -> BalancedRouter<'_, T, S>
+    where
+        T: Eq,
+    {
+        BalancedRouter {
+            ring: self,
+            loads: HashMap::new(),
+            total: 0,
+            nodes: self.physical_node_count(),
+            capacity_factor: capacity_factor.max(1.0),
+        }
+    }
+
+    /// Returns a router caching the owners of up to `capacity` recently routed
+    /// keys. The cache is a list scanned on every lookup, so lookups take
+    /// `O(capacity)` time, and `capacity` is meant to be small.
+    pub fn caching_router(&self, capacity: usize) -> CachingRouter<S> {
+        CachingRouter {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            ring: self.id,
+            version: self.version(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        siphasher::sip::SipHasher,
+        std::{
+            sync::atomic::{AtomicUsize, Ordering},
+            thread,
+        },
+    };
+
+    #[test]
+    fn concurrent_routing() {
+        let mut ring: HashRing<u32> = HashRing::new();
+        for node in 0..16 {
+            ring.add_node(node).unwrap();
+        }
+
+        let expected = (0..1000)
+            .map(|key| ring.owner_index(&key).unwrap())
+            .collect::<Vec<_>>();
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let router = ring.thread_local_router();
+
+                    for (key, expected) in expected.iter().enumerate() {
+                        assert_eq!(router.route(&(key as i32)).unwrap(), *expected);
+                    }
+                });
+            }
+        });
+
+        let ring: HashRing<u32> = HashRing::new();
+        assert_eq!(
+            ring.thread_local_router().route(&1),
+            Err(Error::NodeNotFound)
+        );
+    }
+
+    #[test]
+    fn thread_local_setup() {
+        static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Clone)]
+        struct CountingHashBuilder;
+
+        impl BuildHasher for CountingHashBuilder {
+            type Hasher = SipHasher;
+
+            fn build_hasher(&self) -> Self::Hasher {
+                BUILDS.fetch_add(1, Ordering::Relaxed);
+                SipHasher::new()
+            }
+        }
+
+        let mut ring = HashRing::with_hasher(CountingHashBuilder);
+        for node in 0..16u32 {
+            ring.add_node(node).unwrap();
+        }
+
+        let expected = (0..1000)
+            .map(|key| ring.owner_index(&key).unwrap())
+            .collect::<Vec<_>>();
+        let builds = BUILDS.load(Ordering::Relaxed);
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let router = ring.thread_local_router();
+
+                    for (key, expected) in expected.iter().enumerate() {
+                        assert_eq!(router.route(&(key as i32)).unwrap(), *expected);
+                    }
+                });
+            }
+        });
+
+        // The hasher is only built once per router.
+        assert_eq!(BUILDS.load(Ordering::Relaxed), builds + 4);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn balanced_router() {
+        let mut ring: HashRing<u32> = HashRing::new();
+        assert_eq!(
+            ring.balanced_router(1.25).route(&"foo").unwrap_err(),
+            Error::NodeNotFound
+        );
+
+        for node in 0..8 {
+            ring.add_node(node).unwrap();
+        }
+
+        // Without load, keys go to their owner.
+        assert_eq!(
+            ring.balanced_router(1.25).route(&"foo").unwrap().index,
+            ring.owner_index(&"foo").unwrap()
+        );
+
+        // A single hot key spills over to the following nodes.
+        let mut router = ring.balanced_router(1.25);
+        for _ in 0..800 {
+            router.route(&"foo").unwrap();
+        }
+
+        assert_eq!(router.loads().values().sum::<u64>(), 800);
+        assert!(router.loads().values().all(|&load| load <= 125));
+
+        // Released load makes room on the owner again.
+        let owner = *ring.get_by_hash(&"foo").unwrap().data();
+        let load = router.load(&owner);
+        router.release(&owner);
+        assert_eq!(router.load(&owner), load - 1);
+        assert_eq!(router.route(&"foo").unwrap().data(), &owner);
+
+        // Skewed keys.
+        let mut router = ring.balanced_router(1.0);
+        for key in 0..1000 {
+            router.route(&(key % 10)).unwrap();
+        }
+
+        assert_eq!(router.loads().len(), 8);
+        assert!(router.loads().values().all(|&load| load <= 125));
+    }
+
+    #[test]
+    fn caching_router() {
+        let mut ring: HashRing<u32> = HashRing::new();
+        for node in 0..16 {
+            ring.add_node(node).unwrap();
+        }
+
+        let mut router = ring.caching_router(2);
+
+        for _ in 0..3 {
+            for key in ["foo", "bar"] {
+                assert_eq!(
+                    router.route(&ring, &key).unwrap(),
+                    ring.owner_index(&key).unwrap()
+                );
+            }
+        }
+
+        assert_eq!(router.misses(), 2);
+        assert_eq!(router.hits(), 4);
+
+        // The least recently used entry is evicted.
+        router.route(&ring, &"baz").unwrap();
+        router.route(&ring, &"bar").unwrap();
+        router.route(&ring, &"foo").unwrap();
+        assert_eq!(router.misses(), 4);
+        assert_eq!(router.hits(), 5);
+
+        // Topology changes invalidate the cache.
+        let owner = *ring.get_by_hash(&"foo").unwrap().data();
+        ring.remove_node(&owner).unwrap();
+
+        assert_eq!(
+            router.route(&ring, &"foo").unwrap(),
+            ring.owner_index(&"foo").unwrap()
+        );
+        assert_eq!(router.misses(), 5);
+
+        // Other rings at the same version don't use the cache either.
+        let mut other: HashRing<u32> = HashRing::new();
+        for node in 100..100 + ring.version() as u32 {
+            other.add_node_unchecked(node);
+        }
+        assert_eq!(other.version(), ring.version());
+
+        assert_eq!(
+            router.route(&other, &"foo").unwrap(),
+            other.owner_index(&"foo").unwrap()
+        );
+        assert_eq!(router.misses(), 6);
+
+        let clone = ring.clone();
+        router.route(&clone, &"foo").unwrap();
+        assert_eq!(router.misses(), 7);
+
+        let ring: HashRing<u32> = HashRing::new();
+        assert_eq!(
+            ring.caching_router(2).route(&ring, &1),
+            Err(Error::NodeNotFound)
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use {
+    super::{next_ring_id, HashRing, Node, RingHasher, WeightedPoint},
+    alloc::vec::Vec,
+    serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer},
+};
+
+/// SerializedNode is an internal struct holding a node as it is serialized,
+/// along with its point if it was added with `HashRing::add_weighted_node()`.
+#[derive(Serialize, Deserialize)]
+struct SerializedNode<K, T> {
+    key: K,
+    data: T,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    weight: Option<SerializedWeight>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedWeight {
+    point: u32,
+    total: u32,
+}
+
+/// Serializes the ring's nodes, along with their keys, in ring order. The hash
+/// builder is not serialized.
+impl<T, S> Serialize for HashRing<T, S>
+where
+    T: Serialize,
+    S: RingHasher,
+    S::Key: Serialize,
+{
+    fn serialize<Se: Serializer>(&self, serializer: Se) -> Result<Se::Ok, Se::Error> {
+        serializer.collect_seq(self.data.iter().map(|node| {
+            let weight = self
+                .weights
+                .binary_search_by(|weighted| weighted.key.cmp(&node.key))
+                .ok()
+                .map(|index| SerializedWeight {
+                    point: self.weights[index].point,
+                    total: self.weights[index].total,
+                });
+
+            SerializedNode {
+                key: &node.key,
+                data: &node.data,
+                weight,
+            }
+        }))
+    }
+}
+
+/// Restores a ring from its serialized nodes without re-hashing them, using
+/// the default hash builder. Fails if the nodes are not sorted by key, since
+/// lookups rely on it. See `HashRing::verify_keys()` to check that the keys
+/// match the hash builder.
+impl<'de, T, S> Deserialize<'de> for HashRing<T, S>
+where
+    T: Deserialize<'de>,
+    S: RingHasher + Default,
+    S::Key: Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let nodes = Vec::<SerializedNode<S::Key, T>>::deserialize(deserializer)?;
+
+        if nodes.windows(2).any(|pair| pair[0].key > pair[1].key) {
+            return Err(D::Error::custom("hash ring nodes are not sorted by key"));
+        }
+
+        let mut data = Vec::with_capacity(nodes.len());
+        let mut weights = Vec::new();
+
+        for node in nodes {
+            if let Some(weight) = node.weight {
+                weights.push(WeightedPoint {
+                    key: node.key.clone(),
+                    point: weight.point,
+                    total: weight.total,
+                });
+            }
+
+            data.push(Node::new(node.key, node.data));
+        }
+
+        Ok(HashRing {
+            hash_builder: S::default(),
+            weighted_nodes: weights
+                .iter()
+                .filter(|weighted| weighted.point == 0)
+                .count(),
+            data,
+            weights,
+            observer: None,
+            version: 0,
+            id: next_ring_id(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut ring: HashRing<String> = HashRing::new();
+        for node in 0..8 {
+            ring.add_node(format!("node-{node}")).unwrap();
+        }
+        ring.add_weighted_node("weighted".to_owned(), 4).unwrap();
+
+        let json = serde_json::to_string(&ring).unwrap();
+        let restored: HashRing<String> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.fingerprint(), ring.fingerprint());
+        assert_eq!(restored.verify_keys(), Ok(()));
+        assert_eq!(restored.weight_of(&"weighted".to_owned()), Some(4));
+
+        for key in 0..100 {
+            assert_eq!(
+                restored.get_by_hash(&key).unwrap().data(),
+                ring.get_by_hash(&key).unwrap().data()
+            );
+        }
+    }
+
+    #[test]
+    fn unsorted() {
+        let json = r#"[{"key":20,"data":"a"},{"key":10,"data":"b"}]"#;
+        assert!(serde_json::from_str::<HashRing<String>>(json).is_err());
+
+        // Colliding keys are fine.
+        let json = r#"[{"key":10,"data":"a"},{"key":10,"data":"b"}]"#;
+        assert_eq!(
+            serde_json::from_str::<HashRing<String>>(json)
+                .unwrap()
+                .fingerprint(),
+            vec![10, 10]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use {
+    super::{DefaultHashBuilder, HashRing, RingHasher},
+    arc_swap::ArcSwap,
+    std::sync::Arc,
+};
+
+/// A `HashRing` shared between threads, with lock-free reads. Readers take a
+/// snapshot of the current ring with `load()`, which stays valid for as long
+/// as they hold it, while writers replace the ring as a whole with `store()`
+/// or `update()`.
+pub struct SharedHashRing<T, S: RingHasher = DefaultHashBuilder> {
+    ring: ArcSwap<HashRing<T, S>>,
+}
+
+impl<T, S: RingHasher> SharedHashRing<T, S> {
+    /// Creates a `SharedHashRing` holding `ring`.
+    pub fn new(ring: HashRing<T, S>) -> Self {
+        SharedHashRing {
+            ring: ArcSwap::from_pointee(ring),
+        }
+    }
+
+    /// Returns a snapshot of the current ring. Later changes to the shared
+    /// ring are not reflected in the snapshot.
+    #[inline]
+    pub fn load(&self) -> Arc<HashRing<T, S>> {
+        self.ring.load_full()
+    }
+
+    /// Replaces the current ring with `ring`.
+    #[inline]
+    pub fn store(&self, ring: HashRing<T, S>) {
+        self.ring.store(Arc::new(ring));
+    }
+
+    /// Replaces the current ring with a copy modified by `f`. If another
+    /// writer replaces the ring concurrently, `f` is called again on a copy of
+    /// the new ring, so no change is lost.
+    pub fn update<F>(&self, mut f: F)
+    where
+        T: Clone,
+        F: FnMut(&mut HashRing<T, S>),
+    {
+        self.ring.rcu(|ring| {
+            let mut ring = HashRing::clone(ring);
+            f(&mut ring);
+            ring
+        });
+    }
+}
+
+impl<T, S: RingHasher> From<HashRing<T, S>> for SharedHashRing<T, S> {
+    fn from(ring: HashRing<T, S>) -> Self {
+        Self::new(ring)
+    }
+}
+
+impl<T> Default for SharedHashRing<T> {
+    fn default() -> Self {
+        Self::new(HashRing::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{
+            sync::atomic::{AtomicBool, Ordering},
+            thread,
+        },
+    };
+
+    #[test]
+    fn concurrent_reconfiguration() {
+        let shared = SharedHashRing::default();
+        shared.update(|ring| {
+            ring.add_node(0u32).unwrap();
+        });
+
+        let done = AtomicBool::new(false);
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let mut key = 0u64;
+
+                    while !done.load(Ordering::Relaxed) {
+                        let ring = shared.load();
+                        let node = *ring.get_by_hash(&key).unwrap().data();
+
+                        // Lookups on a snapshot are consistent, regardless of
+                        // concurrent writes.
+                        assert!(node < 100);
+                        assert_eq!(ring.get_by_hash(&key).unwrap().data(), &node);
+                        assert!(ring.contains_node(&node));
+
+                        key += 1;
+                    }
+                });
+            }
+
+            scope.spawn(|| {
+                for node in 1..100 {
+                    shared.update(|ring| {
+                        ring.add_node(node).unwrap();
+                    });
+
+                    if node % 3 == 0 {
+                        shared.update(|ring| {
+                            ring.remove_node(&(node - 1)).unwrap();
+                        });
+                    }
+                }
+
+                done.store(true, Ordering::Relaxed);
+            });
+        });
+
+        assert_eq!(shared.load().len(), 67);
+    }
+
+    #[test]
+    fn store() {
+        let shared = SharedHashRing::new(HashRing::new());
+        let snapshot = shared.load();
+
+        let mut ring = HashRing::new();
+        ring.add_node("a").unwrap();
+        shared.store(ring);
+
+        assert!(snapshot.is_empty());
+        assert_eq!(shared.load().get_by_hash(&"foo").unwrap().data(), &"a");
+    }
+}
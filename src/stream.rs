@@ -0,0 +1,55 @@
+use {
+    super::{Error, HashRing, RingHasher},
+    core::hash::Hash,
+    futures::{Stream, StreamExt},
+};
+
+impl<T, S> HashRing<T, S>
+where
+    T: Hash,
+    S: RingHasher,
+{
+    /// Maps a stream of keys to the indices of the nodes containing them, or
+    /// errors if the hash ring is empty. The returned stream borrows the ring,
+    /// so callers needing a `'static` stream can route through a clone of the
+    /// ring moved into an `async` block instead.
+    pub fn route_stream<'a, U, St>(
+        &'a self,
+        stream: St,
+    ) -> impl Stream<Item = Result<usize, Error>> + 'a
+    where
+        U: Hash,
+        St: Stream<Item = U> + 'a,
+    {
+        stream.map(move |key| self.owner_index(&key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, futures::executor::block_on};
+
+    #[test]
+    fn route_stream() {
+        let mut ring: HashRing<u32> = HashRing::new();
+        for node in 0..8 {
+            ring.add_node(node).unwrap();
+        }
+
+        let routed = block_on(
+            ring.route_stream(futures::stream::iter(0..100))
+                .collect::<Vec<_>>(),
+        );
+        let expected = (0..100)
+            .map(|key| ring.owner_index(&key))
+            .collect::<Vec<_>>();
+        assert_eq!(routed, expected);
+
+        let ring: HashRing<u32> = HashRing::new();
+        let routed = block_on(
+            ring.route_stream(futures::stream::iter([1]))
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(routed, vec![Err(Error::NodeNotFound)]);
+    }
+}